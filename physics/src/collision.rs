@@ -1,19 +1,141 @@
 use super::{
-    gravity::{BrushingLeft, BrushingRight, Grounded},
-    layers,
+    gravity::{BrushingLeft, BrushingRight, Ceilinged, Grounded},
+    interpolation::TransformInterpolation,
+    layers::{CollisionLayers, LayerMask, PhysicsLayers},
+    material::PhysicsMaterial,
     prelude::Velocity,
+    sleep::{SleepTimer, Sleeping},
     spatial,
+    stats::PhysicsStats,
+    TimeScales,
 };
 use bevy::{
     ecs::{component::ComponentId, world::DeferredWorld},
     prelude::*,
-    utils::hashbrown::{HashMap, HashSet},
+    utils::hashbrown::HashMap,
 };
 use spatial::SpatialHash;
 use std::{cmp::Ordering, marker::PhantomData};
 
-/// Contains a list of entities which a [`DynamicBody`] with [`layers::CollidesWith<T>`] collided
-/// with this frame for the layer `T`.
+/// A single contact between a [`DynamicBody`] and another body.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Contact {
+    /// The entity collided with.
+    pub entity: Entity,
+    /// The [`CollisionLayers`] memberships of `entity`.
+    pub layers: LayerMask,
+    /// Points away from `entity`, in the direction the body was pushed out.
+    pub normal: Vec2,
+    /// How far the body was overlapping `entity` along `normal`.
+    pub penetration: f32,
+    /// The velocity of the body relative to `entity` before the collision was resolved.
+    pub relative_velocity: Vec2,
+}
+
+/// Contains the [`Contact`]s of a [`DynamicBody`] with every body it collided with this tick.
+///
+/// Each entity has a single contact, the deepest one found during the tick.
+#[derive(Debug, Default, Component)]
+pub struct Collisions(smallvec::SmallVec<[Contact; 4]>);
+
+impl Collisions {
+    pub fn entities(&self) -> impl Iterator<Item = Entity> + '_ {
+        self.0.iter().map(|c| c.entity)
+    }
+
+    /// Entities collided with which are members of any layer in `mask`.
+    pub fn in_layers(&self, mask: LayerMask) -> impl Iterator<Item = Entity> + '_ {
+        self.contacts_in_layers(mask).map(|c| c.entity)
+    }
+
+    pub fn contacts(&self) -> &[Contact] {
+        &self.0
+    }
+
+    /// Contacts with entities which are members of any layer in `mask`.
+    pub fn contacts_in_layers(&self, mask: LayerMask) -> impl Iterator<Item = &Contact> + '_ {
+        self.0.iter().filter(move |c| c.layers.intersects(mask))
+    }
+
+    /// The contact with `entity`, if the body collided with it this tick.
+    pub fn get(&self, entity: Entity) -> Option<&Contact> {
+        self.0.iter().find(|c| c.entity == entity)
+    }
+
+    fn push(&mut self, contact: Contact) {
+        match self.0.iter_mut().find(|c| c.entity == contact.entity) {
+            Some(c) if c.penetration <= contact.penetration => *c = contact,
+            Some(_) => {}
+            None => self.0.push(contact),
+        }
+    }
+}
+
+/// Fires when a [`DynamicBody`] starts colliding with another body.
+#[derive(Debug, Clone, Copy, Event)]
+pub struct CollisionStarted {
+    pub body: Entity,
+    pub contact: Contact,
+}
+
+/// Fires when a [`DynamicBody`] stops colliding with another body, or when either is despawned.
+#[derive(Debug, Clone, Copy, Event)]
+pub struct CollisionEnded {
+    pub body: Entity,
+    pub other: Entity,
+}
+
+pub(crate) fn emit_collision_states(
+    bodies: Query<(Entity, &Collisions)>,
+    mut started: EventWriter<CollisionStarted>,
+    mut ended: EventWriter<CollisionEnded>,
+    mut active: Local<HashMap<Entity, smallvec::SmallVec<[Entity; 4]>>>,
+) {
+    for (body, collisions) in bodies.iter() {
+        let previous = active.entry(body).or_default();
+        for contact in collisions.contacts() {
+            if !previous.contains(&contact.entity) {
+                started.send(CollisionStarted {
+                    body,
+                    contact: *contact,
+                });
+            }
+        }
+
+        for other in previous.iter() {
+            if collisions.get(*other).is_none() {
+                ended.send(CollisionEnded {
+                    body,
+                    other: *other,
+                });
+            }
+        }
+
+        previous.clear();
+        previous.extend(collisions.entities());
+    }
+
+    active.retain(|body, others| {
+        if bodies.contains(*body) {
+            return true;
+        }
+
+        for other in others.iter() {
+            ended.send(CollisionEnded {
+                body: *body,
+                other: *other,
+            });
+        }
+
+        false
+    });
+}
+
+/// Contains a list of entities which a [`DynamicBody`] with
+/// [`super::layers::CollidesWith<T>`] collided with this frame for the layer `T`.
+///
+/// Copied out of [`Collisions`] for every layer registered with
+/// [`super::layers::RegisterPhysicsLayer::register_collision_layer`].
 #[derive(Component)]
 pub struct Collision<T>(smallvec::SmallVec<[Entity; 4]>, PhantomData<T>);
 
@@ -27,9 +149,19 @@ impl<T> Collision<T> {
     pub fn entities(&self) -> &[Entity] {
         &self.0
     }
+}
+
+pub(crate) fn sync_collisions<T: Component>(
+    layers: Res<PhysicsLayers>,
+    mut bodies: Query<(&Collisions, &mut Collision<T>)>,
+) {
+    let Some(mask) = layers.get::<T>() else {
+        return;
+    };
 
-    fn clear(&mut self) {
-        self.0.clear();
+    for (collisions, mut collision) in bodies.iter_mut() {
+        collision.0.clear();
+        collision.0.extend(collisions.in_layers(mask));
     }
 }
 
@@ -44,51 +176,204 @@ impl TotalResolution {
     }
 }
 
-pub(crate) fn clear_resolution(mut q: Query<&mut TotalResolution>) {
+pub(crate) fn clear_resolution(
+    mut q: Query<&mut TotalResolution, Without<Sleeping>>,
+    mut collisions: Query<&mut Collisions, Without<Sleeping>>,
+) {
     for mut res in q.iter_mut() {
         res.0 = Vec2::new(0.0, 0.0);
     }
+
+    for mut collisions in collisions.iter_mut() {
+        collisions.0.clear();
+    }
 }
 
 /// Marks this entity as having a static position throughout the lifetime of the program.
 ///
 /// All [`StaticBody`] entities are added to a [`spatial::SpatialHash`] after spawning.
 ///
-/// Moving a static body entity will NOT result in their collision being updated.
+/// Moving a static body entity will NOT result in their collision being updated, use a
+/// [`KinematicBody`] instead.
 #[derive(Debug, Default, Clone, Copy, Component)]
 #[require(Collider)]
 #[component(on_remove = remove_static_body)]
 pub struct StaticBody;
 
 fn remove_static_body(mut world: DeferredWorld, entity: Entity, _: ComponentId) {
-    if let Some(parent) = world.get::<Parent>(entity) {
-        let Some(global_t) = world.get::<GlobalTransform>(entity) else {
-            return;
-        };
-        let collider = world.get::<Collider>(entity).unwrap();
-        let collider = collider.global_absolute(global_t);
+    if let Some(parent) = world.get::<Parent>(entity).map(|p| p.get()) {
+        if let Some(mut hash) = world.get_mut::<SpatialHash>(parent) {
+            hash.remove(entity);
+        }
+    }
+}
+
+#[derive(Debug, Default, Clone, Copy, Component)]
+#[require(Collider, TransformInterpolation, SleepTimer)]
+#[component(on_remove = spatial::remove_from_broadphase)]
+pub struct DynamicBody;
+
+/// Makes a [`StaticBody`] or [`KinematicBody`] solid only from above, letting bodies jump up
+/// through it and land on top.
+#[derive(Debug, Default, Clone, Copy, Component)]
+pub struct OneWay;
+
+/// Lets a [`DynamicBody`] fall through [`OneWay`] colliders.
+///
+/// Removed once the body is no longer touching any one way collider.
+#[derive(Debug, Default, Clone, Copy, Component)]
+pub struct DropThrough;
+
+/// How far below the top of a [`OneWay`] collider a body can be, in addition to the distance it
+/// fell this step, and still land on it.
+const ONE_WAY_SKIN: f32 = 0.5;
+
+/// Returns the upward resolution landing `collider` on the one way `platform`, if `collider`
+/// was above `platform` before falling `fall` units this step.
+fn one_way_resolution(
+    collider: &AbsoluteCollider,
+    platform: &AbsoluteCollider,
+    fall: f32,
+) -> Option<Vec2> {
+    let depth = platform.max_y() - collider.min_y();
+    (0. ..=fall + ONE_WAY_SKIN)
+        .contains(&depth)
+        .then_some(Vec2::new(0., depth))
+}
+
+pub(crate) fn clear_drop_through(
+    mut commands: Commands,
+    map_query: Query<&SpatialHash>,
+    one_way: Query<(), With<OneWay>>,
+    dynamic_bodies: Query<(Entity, &GlobalTransform, &Collider), With<DropThrough>>,
+) {
+    for (entity, global_transform, collider) in dynamic_bodies.iter() {
+        let collider = collider.global_absolute(global_transform);
+        let touching = map_query.iter().any(|map| {
+            map.nearby_objects(&collider.position())
+                .any(|d| one_way.contains(d.entity) && collider.collides_with(&d.collider))
+        });
 
-        if let Some(mut hash) = world.get_mut::<SpatialHash>(parent.get()) {
-            hash.remove(collider);
+        if !touching {
+            commands.entity(entity).remove::<DropThrough>();
         }
     }
 }
 
+/// Marks this entity as a body that is moved by scripts or tweens instead of collisions.
+///
+/// Like a [`StaticBody`], a kinematic body must be the child of an entity with a
+/// [`spatial::SpatialHash`]. Its entry is updated every step and [`DynamicBody`] entities
+/// standing on or brushing it are carried along with it.
 #[derive(Debug, Default, Clone, Copy, Component)]
 #[require(Collider)]
-pub struct DynamicBody;
+#[component(on_remove = remove_kinematic_body)]
+pub struct KinematicBody {
+    previous: Option<AbsoluteCollider>,
+}
+
+fn remove_kinematic_body(mut world: DeferredWorld, entity: Entity, _: ComponentId) {
+    if let Some(parent) = world.get::<Parent>(entity).map(|p| p.get()) {
+        if let Some(mut hash) = world.get_mut::<SpatialHash>(parent) {
+            hash.remove(entity);
+        }
+    }
+}
+
+pub(crate) fn update_kinematic_bodies(
+    mut maps: Query<&mut SpatialHash>,
+    mut kinematic_bodies: Query<(
+        Entity,
+        &GlobalTransform,
+        &Collider,
+        &Parent,
+        &mut KinematicBody,
+    )>,
+    mut riders: Query<
+        (
+            &mut Transform,
+            &mut GlobalTransform,
+            &Collider,
+            Has<Grounded>,
+            Has<BrushingLeft>,
+            Has<BrushingRight>,
+        ),
+        (
+            With<DynamicBody>,
+            Without<KinematicBody>,
+            Or<(With<Grounded>, With<BrushingLeft>, With<BrushingRight>)>,
+        ),
+    >,
+) {
+    for (entity, global_transform, collider, parent, mut body) in kinematic_bodies.iter_mut() {
+        let Ok(mut map) = maps.get_mut(parent.get()) else {
+            continue;
+        };
+
+        let current = collider.global_absolute(global_transform);
+        let data = spatial::SpatialData {
+            entity,
+            collider: current,
+            data: (),
+        };
+
+        match body.previous {
+            None => map.insert(data),
+            Some(previous) => {
+                let displacement = current.position() - previous.position();
+                if displacement == Vec2::ZERO {
+                    continue;
+                }
+
+                map.update(data);
+
+                for (mut transform, mut global_transform, collider, grounded, left, right) in
+                    riders.iter_mut()
+                {
+                    let rider = collider.global_absolute(&global_transform);
+                    let carried = (grounded
+                        && rider.contact_normal(&previous, Vec2::NEG_Y * 0.5).y > 0.7)
+                        || (left && rider.contact_normal(&previous, Vec2::NEG_X * 0.1).x > 0.7)
+                        || (right && rider.contact_normal(&previous, Vec2::X * 0.1).x < -0.7);
+
+                    if carried {
+                        let mut global_t = global_transform.compute_transform();
+                        transform.translation += displacement.extend(0.);
+                        global_t.translation += displacement.extend(0.);
+                        *global_transform = GlobalTransform::from(global_t);
+                    }
+                }
+            }
+        }
+
+        body.previous = Some(current);
+    }
+}
 
 /// Prevents a dynamic body entity from being pushed.
 #[derive(Debug, Default, Clone, Copy, Component)]
 pub struct Massive;
 
+/// Sweeps a [`DynamicBody`] along its motion for the current step against static geometry,
+/// stopping it at the first contact instead of letting it tunnel through thin colliders.
+///
+/// Intended for fast movers, it is more expensive than the discrete resolution in
+/// [`handle_collisions`]. Bodies are swept as their bounding boxes.
+#[derive(Debug, Default, Clone, Copy, Component)]
+pub struct ContinuousCollision {
+    /// Displacement applied by [`super::velocity::apply_velocity`] this step.
+    pub(crate) motion: Vec2,
+}
+
 /// To check for collisions, first convert this enum into an [`AbsoluteCollider`]
 /// with [`Collider::absolute`].
 #[derive(Debug, Clone, Copy, PartialEq, Component)]
 #[require(TotalResolution)]
+#[component(on_remove = spatial::remove_from_broadphase)]
 pub enum Collider {
     Rect(RectCollider),
     Circle(CircleCollider),
+    Polygon(PolygonCollider),
 }
 
 impl Default for Collider {
@@ -106,6 +391,22 @@ impl Collider {
         Self::Circle(CircleCollider { position, radius })
     }
 
+    /// Creates a convex polygon from at most [`MAX_POLYGON_POINTS`] points in winding order.
+    pub fn from_polygon(points: &[Vec2]) -> Self {
+        Self::Polygon(PolygonCollider::new(points))
+    }
+
+    /// Creates a right triangle with its hypotenuse facing up, spanning the rect at `tl`.
+    pub fn from_ramp(tl: Vec2, size: Vec2, ramp: Ramp) -> Self {
+        let bl = Vec2::new(tl.x, tl.y - size.y);
+        let br = Vec2::new(tl.x + size.x, tl.y - size.y);
+
+        Self::from_polygon(&match ramp {
+            Ramp::Right => [bl, br, Vec2::new(tl.x + size.x, tl.y)],
+            Ramp::Left => [bl, br, tl],
+        })
+    }
+
     pub fn absolute(&self, transform: &Transform) -> AbsoluteCollider {
         match self {
             Self::Rect(rect) => AbsoluteCollider::Rect(RectCollider {
@@ -116,6 +417,9 @@ impl Collider {
                 position: circle.position + transform.translation.xy(),
                 radius: circle.radius,
             }),
+            Self::Polygon(polygon) => {
+                AbsoluteCollider::Polygon(polygon.translated(transform.translation.xy()))
+            }
         }
     }
 
@@ -129,6 +433,9 @@ impl Collider {
                 position: circle.position + transform.translation().xy(),
                 radius: circle.radius,
             }),
+            Self::Polygon(polygon) => {
+                AbsoluteCollider::Polygon(polygon.translated(transform.translation().xy()))
+            }
         }
     }
 }
@@ -137,6 +444,7 @@ impl Collider {
 pub enum AbsoluteCollider {
     Rect(RectCollider),
     Circle(CircleCollider),
+    Polygon(PolygonCollider),
 }
 
 impl AbsoluteCollider {
@@ -144,13 +452,24 @@ impl AbsoluteCollider {
         match self {
             Self::Rect(rect) => Self::Rect(rect.expand(factor)),
             Self::Circle(circle) => Self::Circle(circle.expand(factor)),
+            Self::Polygon(polygon) => Self::Polygon(polygon.expand(factor)),
         }
     }
 
     pub fn get_aabb(&self) -> RectCollider {
         match self {
             Self::Rect(rect) => *rect,
-            Self::Circle(_) => todo!("implement aabb for circles"),
+            Self::Circle(circle) => RectCollider {
+                tl: Vec2::new(
+                    circle.position.x - circle.radius,
+                    circle.position.y + circle.radius,
+                ),
+                size: Vec2::splat(circle.radius * 2.),
+            },
+            Self::Polygon(_) => RectCollider {
+                tl: Vec2::new(self.min_x(), self.max_y()),
+                size: Vec2::new(self.max_x() - self.min_x(), self.max_y() - self.min_y()),
+            },
         }
     }
 
@@ -158,6 +477,7 @@ impl AbsoluteCollider {
         match self {
             Self::Rect(rect) => rect.tl,
             Self::Circle(circle) => circle.position,
+            Self::Polygon(_) => Vec2::new(self.min_x(), self.max_y()),
         }
     }
 
@@ -165,20 +485,63 @@ impl AbsoluteCollider {
         match self {
             Self::Rect(r) => r.center(),
             Self::Circle(c) => c.position,
+            Self::Polygon(p) => p.center(),
         }
     }
 
     pub fn contains(&self, point: &Vec2) -> bool {
         match self {
             Self::Rect(r) => r.contains(point),
-            _ => todo!(),
+            Self::Circle(c) => c.position.distance_squared(*point) < c.radius * c.radius,
+            Self::Polygon(p) => p.contains(point),
+        }
+    }
+
+    /// Returns the distance along `direction` and surface normal of the first point where a ray
+    /// starting at `origin` enters `self`.
+    ///
+    /// `direction` must be normalized. Rays starting inside `self` hit immediately.
+    pub fn ray_intersection(&self, origin: Vec2, direction: Vec2) -> Option<(f32, Vec2)> {
+        match self {
+            Self::Rect(rect) => rect.ray_intersection(origin, direction),
+            Self::Circle(circle) => circle.ray_intersection(origin, direction),
+            Self::Polygon(polygon) => polygon.ray_intersection(origin, direction),
+        }
+    }
+
+    pub fn translated(&self, offset: Vec2) -> Self {
+        match self {
+            Self::Rect(rect) => Self::Rect(RectCollider {
+                tl: rect.tl + offset,
+                size: rect.size,
+            }),
+            Self::Circle(circle) => Self::Circle(CircleCollider {
+                position: circle.position + offset,
+                radius: circle.radius,
+            }),
+            Self::Polygon(polygon) => Self::Polygon(polygon.translated(offset)),
+        }
+    }
+
+    /// Returns the normal of `other`'s surface if `self` touches it after moving by `probe`,
+    /// otherwise [`Vec2::ZERO`].
+    pub fn contact_normal(&self, other: &Self, probe: Vec2) -> Vec2 {
+        let probed = self.translated(probe);
+        if !probed.collides_with(other) {
+            return Vec2::ZERO;
         }
+
+        probed.resolution(other).normalize_or_zero()
     }
 
     pub fn max_x(&self) -> f32 {
         match self {
             Self::Rect(rect) => rect.tl.x + rect.size.x,
             Self::Circle(circle) => circle.position.x + circle.radius,
+            Self::Polygon(polygon) => polygon
+                .points()
+                .iter()
+                .fold(f32::NEG_INFINITY, |m, p| m.max(p.x)),
         }
     }
 
@@ -186,6 +549,10 @@ impl AbsoluteCollider {
         match self {
             Self::Rect(rect) => rect.tl.x,
             Self::Circle(circle) => circle.position.x - circle.radius,
+            Self::Polygon(polygon) => polygon
+                .points()
+                .iter()
+                .fold(f32::INFINITY, |m, p| m.min(p.x)),
         }
     }
 
@@ -193,6 +560,10 @@ impl AbsoluteCollider {
         match self {
             Self::Rect(rect) => rect.tl.y,
             Self::Circle(circle) => circle.position.y + circle.radius,
+            Self::Polygon(polygon) => polygon
+                .points()
+                .iter()
+                .fold(f32::NEG_INFINITY, |m, p| m.max(p.y)),
         }
     }
 
@@ -200,6 +571,10 @@ impl AbsoluteCollider {
         match self {
             Self::Rect(rect) => rect.tl.y - rect.size.y,
             Self::Circle(circle) => circle.position.y - circle.radius,
+            Self::Polygon(polygon) => polygon
+                .points()
+                .iter()
+                .fold(f32::INFINITY, |m, p| m.min(p.y)),
         }
     }
 }
@@ -211,6 +586,11 @@ impl CollidesWith<Self> for AbsoluteCollider {
             (Self::Rect(s), Self::Circle(o)) => s.collides_with(o),
             (Self::Circle(s), Self::Rect(o)) => s.collides_with(o),
             (Self::Circle(s), Self::Circle(o)) => s.collides_with(o),
+            (Self::Polygon(s), Self::Polygon(o)) => s.collides_with(o),
+            (Self::Polygon(s), Self::Rect(o)) => s.collides_with(o),
+            (Self::Polygon(s), Self::Circle(o)) => s.collides_with(o),
+            (Self::Rect(s), Self::Polygon(o)) => o.collides_with(s),
+            (Self::Circle(s), Self::Polygon(o)) => o.collides_with(s),
         }
     }
 
@@ -220,6 +600,11 @@ impl CollidesWith<Self> for AbsoluteCollider {
             (Self::Rect(s), Self::Circle(o)) => s.resolution(o),
             (Self::Circle(s), Self::Rect(o)) => s.resolution(o),
             (Self::Circle(s), Self::Circle(o)) => s.resolution(o),
+            (Self::Polygon(s), Self::Polygon(o)) => s.resolution(o),
+            (Self::Polygon(s), Self::Rect(o)) => s.resolution(o),
+            (Self::Polygon(s), Self::Circle(o)) => s.resolution(o),
+            (Self::Rect(s), Self::Polygon(o)) => -o.resolution(s),
+            (Self::Circle(s), Self::Polygon(o)) => -o.resolution(s),
         }
     }
 }
@@ -248,6 +633,18 @@ impl RectCollider {
         self.tl.x < point.x && self.br().x > point.x && self.tl.y > point.y && self.br().y < point.y
     }
 
+    /// The smallest rect containing `self` before and after moving by `motion`.
+    pub fn swept(&self, motion: Vec2) -> Self {
+        let min = Vec2::new(self.tl.x, self.br().y);
+        let max = Vec2::new(self.br().x, self.tl.y);
+        let (min, max) = (min.min(min + motion), max.max(max + motion));
+
+        Self {
+            tl: Vec2::new(min.x, max.y),
+            size: max - min,
+        }
+    }
+
     pub fn br(&self) -> Vec2 {
         Vec2::new(self.tl.x + self.size.x, self.tl.y - self.size.y)
     }
@@ -256,6 +653,88 @@ impl RectCollider {
         Vec2::new(self.tl.x + self.size.x * 0.5, self.tl.y - self.size.y * 0.5)
     }
 
+    /// Casts `self` along `motion` against `other`.
+    ///
+    /// Returns the fraction of `motion` travelled before first contact along with the contact
+    /// normal of `other`. Rects that are already overlapping, only sliding along an edge or
+    /// grazing a corner do not produce a hit. Contacts within [`SWEEP_TOLERANCE`] of the start
+    /// are hit immediately.
+    pub fn sweep(&self, motion: Vec2, other: &RectCollider) -> Option<(f32, Vec2)> {
+        // Cast the bottom left corner of `self` against `other` expanded by `self.size`.
+        let origin = Vec2::new(self.tl.x, self.br().y);
+        let min = Vec2::new(other.tl.x - self.size.x, other.br().y - self.size.y);
+        let max = Vec2::new(other.br().x, other.tl.y);
+
+        let mut entry = Vec2::splat(f32::NEG_INFINITY);
+        let mut exit = Vec2::splat(f32::INFINITY);
+        for axis in 0..2 {
+            if motion[axis] == 0. {
+                if origin[axis] <= min[axis] || origin[axis] >= max[axis] {
+                    return None;
+                }
+            } else {
+                let t0 = (min[axis] - origin[axis]) / motion[axis];
+                let t1 = (max[axis] - origin[axis]) / motion[axis];
+                entry[axis] = t0.min(t1);
+                exit[axis] = t0.max(t1);
+            }
+        }
+
+        let t_entry = entry.x.max(entry.y);
+        let t_exit = exit.x.min(exit.y);
+        let tolerance = SWEEP_TOLERANCE / motion.length();
+        if t_entry >= t_exit - tolerance || !(-tolerance..=1.).contains(&t_entry) {
+            return None;
+        }
+
+        let normal = if entry.x > entry.y {
+            Vec2::new(-motion.x.signum(), 0.)
+        } else {
+            Vec2::new(0., -motion.y.signum())
+        };
+
+        Some((t_entry.max(0.), normal))
+    }
+
+    /// See [`AbsoluteCollider::ray_intersection`].
+    pub fn ray_intersection(&self, origin: Vec2, direction: Vec2) -> Option<(f32, Vec2)> {
+        let min = Vec2::new(self.tl.x, self.br().y);
+        let max = Vec2::new(self.br().x, self.tl.y);
+
+        let mut entry = Vec2::splat(f32::NEG_INFINITY);
+        let mut exit = Vec2::splat(f32::INFINITY);
+        for axis in 0..2 {
+            if direction[axis] == 0. {
+                if origin[axis] < min[axis] || origin[axis] > max[axis] {
+                    return None;
+                }
+            } else {
+                let t0 = (min[axis] - origin[axis]) / direction[axis];
+                let t1 = (max[axis] - origin[axis]) / direction[axis];
+                entry[axis] = t0.min(t1);
+                exit[axis] = t0.max(t1);
+            }
+        }
+
+        let t_entry = entry.x.max(entry.y);
+        let t_exit = exit.x.min(exit.y);
+        if t_entry > t_exit || t_exit <= 0. {
+            return None;
+        }
+
+        if t_entry < 0. {
+            return Some((0., -direction));
+        }
+
+        let normal = if entry.x > entry.y {
+            Vec2::new(-direction.x.signum(), 0.)
+        } else {
+            Vec2::new(0., -direction.y.signum())
+        };
+
+        Some((t_entry, normal))
+    }
+
     pub fn line_intersection(&self, p0: Vec2, p1: Vec2) -> Option<Vec2> {
         // Parametric direction
         let dir = p1 - p0;
@@ -382,6 +861,32 @@ impl CircleCollider {
     }
 }
 
+impl CircleCollider {
+    /// See [`AbsoluteCollider::ray_intersection`].
+    pub fn ray_intersection(&self, origin: Vec2, direction: Vec2) -> Option<(f32, Vec2)> {
+        let offset = origin - self.position;
+        let b = offset.dot(direction);
+        let c = offset.length_squared() - self.radius * self.radius;
+
+        if c > 0. && b > 0. {
+            return None;
+        }
+
+        let discriminant = b * b - c;
+        if discriminant < 0. {
+            return None;
+        }
+
+        let t = -b - discriminant.sqrt();
+        if t < 0. {
+            return Some((0., -direction));
+        }
+
+        let normal = (origin + direction * t - self.position).normalize_or_zero();
+        Some((t, normal))
+    }
+}
+
 impl CollidesWith<Self> for CircleCollider {
     fn collides_with(&self, other: &Self) -> bool {
         let distance = self.position.distance_squared(other.position);
@@ -447,10 +952,12 @@ impl CollidesWith<RectCollider> for CircleCollider {
     }
 
     fn resolution(&self, other: &RectCollider) -> Vec2 {
+        let br = other.br();
+
         // Find the closest point on the rectangle to the circle's center
         let closest = Vec2::new(
-            self.position.x.clamp(other.tl.x, other.tl.x + other.size.x),
-            self.position.y.clamp(other.tl.y, other.tl.y + other.size.y),
+            self.position.x.clamp(other.tl.x, br.x),
+            self.position.y.clamp(br.y, other.tl.y),
         );
 
         let diff = self.position - closest;
@@ -461,26 +968,26 @@ impl CollidesWith<RectCollider> for CircleCollider {
             return Vec2::ZERO;
         }
 
-        // Handle case where circle center is exactly on rectangle edge
+        // Handle case where circle center is inside the rectangle
         if distance == 0.0 {
             // Find which edge we're closest to and push out accordingly
             let to_left = self.position.x - other.tl.x;
-            let to_right = (other.tl.x + other.size.x) - self.position.x;
-            let to_top = self.position.y - other.tl.y;
-            let to_bottom = (other.tl.y + other.size.y) - self.position.y;
+            let to_right = br.x - self.position.x;
+            let to_top = other.tl.y - self.position.y;
+            let to_bottom = self.position.y - br.y;
 
             let min_dist = to_left.min(to_right).min(to_top).min(to_bottom);
 
             if min_dist == to_left {
-                return Vec2::new(-self.radius, 0.0);
+                return Vec2::new(-(to_left + self.radius), 0.0);
             }
             if min_dist == to_right {
-                return Vec2::new(self.radius, 0.0);
+                return Vec2::new(to_right + self.radius, 0.0);
             }
             if min_dist == to_top {
-                return Vec2::new(0.0, -self.radius);
+                return Vec2::new(0.0, to_top + self.radius);
             }
-            return Vec2::new(0.0, self.radius);
+            return Vec2::new(0.0, -(to_bottom + self.radius));
         }
 
         // Calculate the overlap and direction
@@ -498,147 +1005,787 @@ impl CollidesWith<CircleCollider> for RectCollider {
     }
 
     fn resolution(&self, other: &CircleCollider) -> Vec2 {
-        other.resolution(self)
+        -other.resolution(self)
     }
 }
 
-pub fn handle_collisions<T: Component>(
-    map_query: Query<&SpatialHash, With<T>>,
-    mut dynamic_bodies: Query<
-        (
-            &mut GlobalTransform,
-            &mut Transform,
-            &Collider,
-            &mut Velocity,
-            &mut TotalResolution,
-            &mut Collision<T>,
-        ),
-        (With<DynamicBody>, With<layers::CollidesWith<T>>),
-    >,
-) {
-    for (
-        mut global_transform,
-        mut transform,
-        collider,
-        mut velocity,
-        mut resolution,
-        mut entity_collision,
-    ) in dynamic_bodies.iter_mut()
-    {
-        let original_collider = &collider;
-        let mut global_t = global_transform.compute_transform();
-        let mut collider = collider.absolute(&global_t);
-        entity_collision.clear();
+/// The maximum number of points in a [`PolygonCollider`].
+pub const MAX_POLYGON_POINTS: usize = 8;
 
-        for map in map_query.iter() {
-            let mut colliders = map.nearby_objects(&collider.position()).collect::<Vec<_>>();
+/// The distance along a sweep within which contacts are considered simultaneous.
+const SWEEP_TOLERANCE: f32 = 0.05;
 
-            colliders.sort_by(|d1, d2| {
-                let d1 = collider.resolution(&d1.collider).length_squared();
-                let d2 = collider.resolution(&d2.collider).length_squared();
+/// The side of a [`Collider::from_ramp`] triangle that is raised.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Ramp {
+    Left,
+    Right,
+}
 
-                d2.partial_cmp(&d1).unwrap_or(Ordering::Equal)
-            });
+/// A convex polygon, resolved with the separating axis theorem.
+#[derive(Debug, Clone, Copy, PartialEq, Component)]
+pub struct PolygonCollider {
+    points: [Vec2; MAX_POLYGON_POINTS],
+    len: usize,
+}
 
-            for spatial::SpatialData {
-                entity,
-                collider: sc,
-                ..
-            } in colliders.into_iter()
-            {
-                if collider.collides_with(&sc) {
-                    entity_collision.0.push(*entity);
+impl PolygonCollider {
+    /// # Panics
+    ///
+    /// Panics if `points` has fewer than 3 or more than [`MAX_POLYGON_POINTS`] points.
+    pub fn new(points: &[Vec2]) -> Self {
+        assert!(
+            (3..=MAX_POLYGON_POINTS).contains(&points.len()),
+            "polygon colliders require between 3 and {MAX_POLYGON_POINTS} points"
+        );
 
-                    let res = collider.resolution(&sc);
-                    resolution.0 += res;
+        let mut slf = Self {
+            points: [Vec2::ZERO; MAX_POLYGON_POINTS],
+            len: points.len(),
+        };
+        slf.points[..points.len()].copy_from_slice(points);
+        slf
+    }
 
-                    let res = res.extend(0.0);
-                    transform.translation += res;
-                    global_t.translation += res;
-                    collider = original_collider.absolute(&global_t);
+    pub fn points(&self) -> &[Vec2] {
+        &self.points[..self.len]
+    }
 
-                    if res.y.abs() > 0. {
-                        velocity.0.y = 0.;
-                    }
-                }
-            }
-        }
+    pub fn translated(mut self, offset: Vec2) -> Self {
+        self.points[..self.len]
+            .iter_mut()
+            .for_each(|p| *p += offset);
+        self
+    }
 
-        // update global transform here so changes are observable in remaining collision systems.
-        *global_transform = GlobalTransform::from(global_t);
+    pub fn expand(mut self, factor: f32) -> Self {
+        let center = self.center();
+        self.points[..self.len]
+            .iter_mut()
+            .for_each(|p| *p = center + (*p - center) * factor);
+        self
     }
-}
 
-pub fn update_grounded<T: Component>(
-    mut commands: Commands,
-    map_query: Query<&SpatialHash, With<T>>,
-    mut dynamic_bodies: Query<
-        (Entity, &GlobalTransform, &Collider, &Velocity),
-        (With<DynamicBody>, With<super::layers::CollidesWith<T>>),
-    >,
-) {
-    for (entity, global_transform, collider, velocity) in dynamic_bodies.iter_mut() {
-        let mut grounded = false;
+    pub fn center(&self) -> Vec2 {
+        self.points().iter().sum::<Vec2>() / self.len as f32
+    }
 
-        for map in map_query.iter() {
-            let collider = collider.global_absolute(global_transform);
-            let nearby_colliders = map.nearby_objects(&collider.position());
+    pub fn contains(&self, point: &Vec2) -> bool {
+        self.edges()
+            .map(|(a, b)| (b - a).perp_dot(*point - a))
+            .try_fold(0., |sign: f32, cross| {
+                (cross != 0. && cross.signum() != -sign).then_some(cross.signum())
+            })
+            .is_some()
+    }
 
-            for spatial::SpatialData {
-                collider: static_collider,
-                ..
-            } in nearby_colliders.into_iter()
-            {
-                match (collider, static_collider) {
-                    (AbsoluteCollider::Rect(a), AbsoluteCollider::Rect(b)) => {
-                        let x_range = b.tl.x..b.br().x;
+    /// See [`AbsoluteCollider::ray_intersection`].
+    pub fn ray_intersection(&self, origin: Vec2, direction: Vec2) -> Option<(f32, Vec2)> {
+        let center = self.center();
+        let mut t_entry = f32::NEG_INFINITY;
+        let mut t_exit = f32::INFINITY;
+        let mut entry_normal = -direction;
+
+        for (a, b) in self.edges() {
+            let mut normal = (b - a).perp().normalize_or_zero();
+            if normal.dot(a - center) < 0. {
+                normal = -normal;
+            }
 
-                        let on_top = (a.br().y - b.tl.y).abs() < 0.5;
-                        let corner_inside =
-                            x_range.contains(&a.tl.x) || x_range.contains(&a.br().x);
-                        let no_going_up = velocity.0.y >= 0.;
+            let distance = normal.dot(a - origin);
+            let speed = normal.dot(direction);
+            if speed == 0. {
+                if distance < 0. {
+                    return None;
+                }
+                continue;
+            }
 
-                        if on_top && corner_inside && no_going_up {
-                            grounded = true;
-                            break;
-                        }
-                    }
-                    _ => {
-                        todo!("implement more grounded interactions")
-                    }
+            let t = distance / speed;
+            if speed < 0. {
+                if t > t_entry {
+                    t_entry = t;
+                    entry_normal = normal;
                 }
+            } else {
+                t_exit = t_exit.min(t);
             }
         }
 
-        if grounded {
-            commands.entity(entity).insert(Grounded);
-        } else {
-            commands.entity(entity).remove::<Grounded>();
+        if t_entry > t_exit || t_exit <= 0. {
+            return None;
+        }
+
+        if t_entry < 0. {
+            return Some((0., -direction));
         }
+
+        Some((t_entry, entry_normal))
     }
-}
 
-pub fn update_brushing<T: Component>(
-    mut commands: Commands,
-    map_query: Query<&SpatialHash, With<T>>,
+    /// Casts `self` along `motion` against `other`.
+    ///
+    /// See [`RectCollider::sweep`].
+    pub fn sweep(&self, motion: Vec2, other: &PolygonCollider) -> Option<(f32, Vec2)> {
+        let mut entries = smallvec::SmallVec::<[(f32, Vec2); 2 * MAX_POLYGON_POINTS]>::new();
+        let mut t_exit = f32::INFINITY;
+
+        for axis in self.axes().chain(other.axes()) {
+            if axis == Vec2::ZERO {
+                continue;
+            }
+
+            let (a_min, a_max) = self.project(axis);
+            let (b_min, b_max) = other.project(axis);
+            let speed = motion.dot(axis);
+            // Moving along the axis, only sliding along an edge of `other`.
+            if speed.abs() <= motion.length() * 1e-4 {
+                if a_max <= b_min + SWEEP_TOLERANCE || a_min >= b_max - SWEEP_TOLERANCE {
+                    return None;
+                }
+                continue;
+            }
+
+            let t0 = (b_min - a_max) / speed;
+            let t1 = (b_max - a_min) / speed;
+            entries.push((t0.min(t1), -axis * speed.signum()));
+            t_exit = t_exit.min(t0.max(t1));
+        }
+
+        let t_entry = entries
+            .iter()
+            .fold(f32::NEG_INFINITY, |t, (entry, _)| t.max(*entry));
+        let tolerance = SWEEP_TOLERANCE / motion.length();
+        if t_entry >= t_exit - tolerance || !(-tolerance..=1.).contains(&t_entry) {
+            return None;
+        }
+
+        // Of the surfaces reached at the same time, prefer the most walkable so that bodies
+        // sliding along the ground walk onto ramps instead of catching on their toe.
+        let normal = entries
+            .iter()
+            .filter(|(entry, _)| *entry >= t_entry - tolerance)
+            .map(|(_, normal)| *normal)
+            .max_by(|a, b| a.y.total_cmp(&b.y))?;
+
+        Some((t_entry.max(0.), normal))
+    }
+
+    fn edges(&self) -> impl Iterator<Item = (Vec2, Vec2)> + '_ {
+        let points = self.points();
+        points
+            .iter()
+            .zip(points.iter().cycle().skip(1))
+            .map(|(a, b)| (*a, *b))
+    }
+
+    fn axes(&self) -> impl Iterator<Item = Vec2> + '_ {
+        self.edges()
+            .map(|(a, b)| (b - a).perp().normalize_or_zero())
+    }
+
+    fn project(&self, axis: Vec2) -> (f32, f32) {
+        self.points()
+            .iter()
+            .map(|p| p.dot(axis))
+            .fold((f32::INFINITY, f32::NEG_INFINITY), |(min, max), d| {
+                (min.min(d), max.max(d))
+            })
+    }
+}
+
+impl From<RectCollider> for PolygonCollider {
+    fn from(rect: RectCollider) -> Self {
+        let br = rect.br();
+        Self::new(&[
+            Vec2::new(rect.tl.x, br.y),
+            br,
+            Vec2::new(br.x, rect.tl.y),
+            rect.tl,
+        ])
+    }
+}
+
+/// Finds the smallest vector that pushes shape `a` out of shape `b` along `axes`.
+///
+/// Returns `None` if any axis separates the shapes.
+fn separating_axis(
+    axes: impl Iterator<Item = Vec2>,
+    a: impl Fn(Vec2) -> (f32, f32),
+    b: impl Fn(Vec2) -> (f32, f32),
+    direction: Vec2,
+) -> Option<Vec2> {
+    let mut smallest: Option<(f32, Vec2)> = None;
+    for axis in axes.filter(|axis| *axis != Vec2::ZERO) {
+        let (a_min, a_max) = a(axis);
+        let (b_min, b_max) = b(axis);
+
+        let overlap = a_max.min(b_max) - a_min.max(b_min);
+        if overlap < 0. {
+            return None;
+        }
+
+        if smallest.is_none_or(|(o, _)| overlap < o) {
+            smallest = Some((overlap, axis));
+        }
+    }
+
+    smallest.map(|(overlap, axis)| {
+        if axis.dot(direction) < 0. {
+            -axis * overlap
+        } else {
+            axis * overlap
+        }
+    })
+}
+
+impl CollidesWith<Self> for PolygonCollider {
+    fn collides_with(&self, other: &Self) -> bool {
+        separating_axis(
+            self.axes().chain(other.axes()),
+            |axis| self.project(axis),
+            |axis| other.project(axis),
+            self.center() - other.center(),
+        )
+        .is_some()
+    }
+
+    fn resolution(&self, other: &Self) -> Vec2 {
+        separating_axis(
+            self.axes().chain(other.axes()),
+            |axis| self.project(axis),
+            |axis| other.project(axis),
+            self.center() - other.center(),
+        )
+        .unwrap_or_default()
+    }
+}
+
+impl CollidesWith<RectCollider> for PolygonCollider {
+    fn collides_with(&self, other: &RectCollider) -> bool {
+        self.collides_with(&PolygonCollider::from(*other))
+    }
+
+    fn resolution(&self, other: &RectCollider) -> Vec2 {
+        self.resolution(&PolygonCollider::from(*other))
+    }
+}
+
+impl PolygonCollider {
+    fn circle_separation(&self, circle: &CircleCollider) -> Option<Vec2> {
+        let closest = self
+            .points()
+            .iter()
+            .min_by(|a, b| {
+                a.distance_squared(circle.position)
+                    .total_cmp(&b.distance_squared(circle.position))
+            })
+            .copied()
+            .unwrap_or_default();
+
+        separating_axis(
+            self.axes().chain(std::iter::once(
+                (circle.position - closest).normalize_or_zero(),
+            )),
+            |axis| self.project(axis),
+            |axis| {
+                let center = circle.position.dot(axis);
+                (center - circle.radius, center + circle.radius)
+            },
+            self.center() - circle.position,
+        )
+    }
+}
+
+impl CollidesWith<CircleCollider> for PolygonCollider {
+    fn collides_with(&self, other: &CircleCollider) -> bool {
+        self.circle_separation(other).is_some()
+    }
+
+    fn resolution(&self, other: &CircleCollider) -> Vec2 {
+        self.circle_separation(other).unwrap_or_default()
+    }
+}
+
+pub fn handle_collisions(
+    map_query: Query<(&SpatialHash, &CollisionLayers)>,
+    mut dynamic_bodies: Query<
+        (
+            Entity,
+            &mut GlobalTransform,
+            &mut Transform,
+            &Collider,
+            &mut Velocity,
+            &mut TotalResolution,
+            &CollisionLayers,
+            &mut Collisions,
+            Option<&PhysicsMaterial>,
+            Has<DropThrough>,
+        ),
+        (With<DynamicBody>, Without<Sleeping>),
+    >,
+    others: Query<&Velocity, Without<DynamicBody>>,
+    materials: Query<&PhysicsMaterial>,
+    one_way: Query<(), With<OneWay>>,
+    time: Res<Time>,
+    scales: TimeScales,
+    mut stats: ResMut<PhysicsStats>,
+) {
+    for (
+        entity,
+        mut global_transform,
+        mut transform,
+        collider,
+        mut velocity,
+        mut resolution,
+        layers,
+        mut collisions,
+        material,
+        drop_through,
+    ) in dynamic_bodies.iter_mut()
+    {
+        let material = material.copied().unwrap_or_default();
+        let original_collider = &collider;
+        let mut global_t = global_transform.compute_transform();
+        let mut collider = collider.absolute(&global_t);
+        let fall =
+            (velocity.0.y <= 0.).then(|| -velocity.0.y * time.delta_secs() * scales.of(entity));
+
+        for (map, map_layers) in map_query.iter() {
+            if !layers.collides_with(map_layers) {
+                continue;
+            }
+
+            let mut colliders = map.nearby_objects(&collider.position()).collect::<Vec<_>>();
+
+            colliders.sort_by(|d1, d2| {
+                let d1 = collider.resolution(&d1.collider).length_squared();
+                let d2 = collider.resolution(&d2.collider).length_squared();
+
+                d2.partial_cmp(&d1).unwrap_or(Ordering::Equal)
+            });
+
+            for spatial::SpatialData {
+                entity,
+                collider: sc,
+                ..
+            } in colliders.into_iter()
+            {
+                stats.pair_tests += 1;
+                if collider.collides_with(&sc) {
+                    let (res, contact) = if one_way.contains(*entity) {
+                        match fall.filter(|_| !drop_through) {
+                            Some(fall) => match one_way_resolution(&collider, sc, fall) {
+                                Some(res) => (res, res),
+                                None => continue,
+                            },
+                            None => continue,
+                        }
+                    } else if let AbsoluteCollider::Polygon(_) = sc {
+                        let res = collider.resolution(&sc);
+                        (slope_resolution(res), res)
+                    } else {
+                        let res = collider.resolution(&sc);
+                        (res, res)
+                    };
+
+                    collisions.push(Contact {
+                        entity: *entity,
+                        layers: map_layers.memberships,
+                        normal: contact.normalize_or_zero(),
+                        penetration: contact.length(),
+                        relative_velocity: velocity.0
+                            - others.get(*entity).map(|v| v.0).unwrap_or_default(),
+                    });
+                    resolution.0 += res;
+                    stats.resolutions += 1;
+
+                    let restitution = material
+                        .combine(&materials.get(*entity).copied().unwrap_or_default())
+                        .restitution;
+                    if res.y.abs() > 0. {
+                        velocity.0.y = rebound(velocity.0.y, res.y, restitution);
+                    }
+                    // Walls only affect horizontal velocity when bouncy.
+                    if restitution > 0. && velocity.0.x * res.x < 0. {
+                        velocity.0.x = rebound(velocity.0.x, res.x, restitution);
+                    }
+
+                    let res = res.extend(0.0);
+                    transform.translation += res;
+                    global_t.translation += res;
+                    collider = original_collider.absolute(&global_t);
+                }
+            }
+        }
+
+        // update global transform here so changes are observable in remaining collision systems.
+        *global_transform = GlobalTransform::from(global_t);
+    }
+}
+
+/// The velocity along one axis after a body was pushed out of a surface by `res` along it.
+///
+/// Bodies moving into the surface bounce off of it with `restitution`, otherwise they stop.
+fn rebound(velocity: f32, res: f32, restitution: f32) -> f32 {
+    if velocity * res < 0. {
+        -velocity * restitution
+    } else {
+        0.
+    }
+}
+
+/// The smallest normal y component of a surface that bodies can stand on.
+const WALKABLE_NORMAL: f32 = 0.7;
+
+/// How far [`snap_to_slopes`] will pull a grounded body down to keep it on a slope.
+const SLOPE_SNAP: f32 = 4.;
+
+/// Resolves walkable slopes vertically so that bodies do not slide down them under gravity
+/// and walk up them instead of being pushed back.
+fn slope_resolution(res: Vec2) -> Vec2 {
+    let normal = res.normalize_or_zero();
+    if normal.y > WALKABLE_NORMAL {
+        Vec2::new(0., res.length() / normal.y)
+    } else {
+        res
+    }
+}
+
+/// The number of times [`handle_continuous_collisions`] will slide a body along a surface
+/// after a contact within a single step.
+const MAX_SWEEPS: usize = 4;
+
+pub fn handle_continuous_collisions(
+    map_query: Query<(&SpatialHash, &CollisionLayers)>,
+    mut dynamic_bodies: Query<
+        (
+            &mut GlobalTransform,
+            &mut Transform,
+            &Collider,
+            &mut Velocity,
+            &mut TotalResolution,
+            &mut ContinuousCollision,
+            &CollisionLayers,
+            &mut Collisions,
+            Option<&PhysicsMaterial>,
+            Has<DropThrough>,
+        ),
+        (With<DynamicBody>, Without<Sleeping>),
+    >,
+    others: Query<&Velocity, Without<DynamicBody>>,
+    materials: Query<&PhysicsMaterial>,
+    one_way: Query<(), With<OneWay>>,
+    mut stats: ResMut<PhysicsStats>,
+) {
+    for (
+        mut global_transform,
+        mut transform,
+        collider,
+        mut velocity,
+        mut resolution,
+        mut continuous,
+        layers,
+        mut collisions,
+        material,
+        drop_through,
+    ) in dynamic_bodies.iter_mut()
+    {
+        if continuous.motion == Vec2::ZERO {
+            continue;
+        }
+
+        let material = material.copied().unwrap_or_default();
+        let mut global_t = global_transform.compute_transform();
+        let end = global_t.translation.xy();
+        let start = end - continuous.motion;
+
+        let mut position = start;
+        let mut remaining = continuous.motion;
+        for _ in 0..MAX_SWEEPS {
+            if remaining == Vec2::ZERO {
+                break;
+            }
+
+            let aabb = collider
+                .absolute(&Transform::from_translation(position.extend(0.)))
+                .get_aabb();
+            let swept = aabb.swept(remaining);
+
+            let mut first_hit: Option<(f32, Vec2, Entity, LayerMask)> = None;
+            for (map, map_layers) in map_query.iter() {
+                if !layers.collides_with(map_layers) {
+                    continue;
+                }
+
+                for data in map.objects_in_rect(&swept) {
+                    stats.pair_tests += 1;
+                    let one_way = one_way.contains(data.entity);
+                    if one_way && drop_through {
+                        continue;
+                    }
+
+                    let hit = match data.collider {
+                        AbsoluteCollider::Polygon(polygon) => {
+                            PolygonCollider::from(aabb).sweep(remaining, &polygon)
+                        }
+                        other => aabb.sweep(remaining, &other.get_aabb()),
+                    };
+                    if let Some((t, normal)) = hit {
+                        // One way colliders are only solid when landed on from above.
+                        if one_way && normal != Vec2::Y {
+                            continue;
+                        }
+
+                        if first_hit.is_none_or(|(first, ..)| t < first) {
+                            first_hit = Some((t, normal, data.entity, map_layers.memberships));
+                        }
+                    }
+                }
+            }
+
+            let Some((t, normal, entity, memberships)) = first_hit else {
+                position += remaining;
+                break;
+            };
+
+            // Swept contacts are found before the bodies overlap.
+            collisions.push(Contact {
+                entity,
+                layers: memberships,
+                normal,
+                penetration: 0.,
+                relative_velocity: velocity.0 - others.get(entity).map(|v| v.0).unwrap_or_default(),
+            });
+
+            position += remaining * t;
+            remaining *= 1. - t;
+            let restitution = material
+                .combine(&materials.get(entity).copied().unwrap_or_default())
+                .restitution;
+            let into = velocity.0.dot(normal);
+            if normal.y > WALKABLE_NORMAL {
+                // Like `slope_resolution`, walkable surfaces keep the horizontal motion.
+                remaining.y = -remaining.x * normal.x / normal.y;
+                if into < 0. {
+                    velocity.0.y = rebound(velocity.0.y, normal.y, restitution);
+                }
+            } else {
+                // Slide along the contact surface with whatever motion is left.
+                remaining -= normal * remaining.dot(normal);
+                if into < 0. {
+                    velocity.0 -= normal * into * (1. + restitution);
+                }
+            }
+        }
+
+        if position != end {
+            let res = position - end;
+            resolution.0 += res;
+            stats.resolutions += 1;
+            transform.translation += res.extend(0.);
+            global_t.translation += res.extend(0.);
+            *global_transform = GlobalTransform::from(global_t);
+        }
+
+        continuous.motion = position - start;
+    }
+}
+
+/// Keeps grounded bodies walking down a slope on the ground instead of stepping off of it
+/// every tick.
+pub(crate) fn snap_to_slopes(
+    map_query: Query<(&SpatialHash, &CollisionLayers)>,
+    mut dynamic_bodies: Query<
+        (
+            &mut GlobalTransform,
+            &mut Transform,
+            &Collider,
+            &Velocity,
+            &CollisionLayers,
+            Has<DropThrough>,
+        ),
+        (With<DynamicBody>, With<Grounded>, Without<Sleeping>),
+    >,
+    one_way: Query<(), With<OneWay>>,
+    physics_layers: Res<PhysicsLayers>,
+) {
+    for (mut global_transform, mut transform, collider, velocity, layers, drop_through) in
+        dynamic_bodies.iter_mut()
+    {
+        if velocity.0.y > 0. {
+            continue;
+        }
+
+        let collider = collider.global_absolute(&global_transform);
+        let filters = layers.filters & physics_layers.grounded();
+        let mut snap: Option<f32> = None;
+        'maps: for (map, map_layers) in map_query.iter() {
+            if !filters.intersects(map_layers.memberships) {
+                continue;
+            }
+
+            for data in map.nearby_objects(&collider.position()) {
+                if one_way.contains(data.entity) && drop_through {
+                    continue;
+                }
+
+                // Already on the ground.
+                if collider.contact_normal(&data.collider, Vec2::NEG_Y * 0.5).y > WALKABLE_NORMAL {
+                    snap = None;
+                    break 'maps;
+                }
+
+                let AbsoluteCollider::Polygon(_) = data.collider else {
+                    continue;
+                };
+
+                let probe = collider.translated(Vec2::NEG_Y * SLOPE_SNAP);
+                if !probe.collides_with(&data.collider) {
+                    continue;
+                }
+
+                let res = probe.resolution(&data.collider);
+                if res.normalize_or_zero().y > WALKABLE_NORMAL {
+                    let distance = SLOPE_SNAP - slope_resolution(res).y;
+                    if distance > 0. && snap.is_none_or(|snap| distance < snap) {
+                        snap = Some(distance);
+                    }
+                }
+            }
+        }
+
+        if let Some(distance) = snap {
+            let offset = Vec3::NEG_Y * distance;
+            transform.translation += offset;
+            let mut global_t = global_transform.compute_transform();
+            global_t.translation += offset;
+            *global_transform = GlobalTransform::from(global_t);
+        }
+    }
+}
+
+pub fn update_grounded(
+    mut commands: Commands,
+    map_query: Query<(&SpatialHash, &CollisionLayers)>,
+    broadphase: Res<spatial::DynamicBroadphase>,
+    dynamic_bodies: Query<
+        (
+            Entity,
+            &GlobalTransform,
+            &Collider,
+            &Velocity,
+            &CollisionLayers,
+            Has<DropThrough>,
+        ),
+        (With<DynamicBody>, Without<Sleeping>),
+    >,
+    one_way: Query<(), With<OneWay>>,
+    physics_layers: Res<PhysicsLayers>,
+) {
+    for (entity, global_transform, collider, velocity, layers, drop_through) in
+        dynamic_bodies.iter()
+    {
+        let filters = layers.filters & physics_layers.grounded();
+        let collider = collider.global_absolute(&global_transform);
+        let position = collider.position();
+
+        let ground_on = |ground: Entity, ground_collider: &AbsoluteCollider| {
+            if one_way.contains(ground) && (drop_through || velocity.0.y > 0.) {
+                return None;
+            }
+
+            match (collider, ground_collider) {
+                (AbsoluteCollider::Rect(a), AbsoluteCollider::Rect(b)) => {
+                    let x_range = b.tl.x..b.br().x;
+
+                    let on_top = (a.br().y - b.tl.y).abs() < 0.5;
+                    let corner_inside = x_range.contains(&a.tl.x) || x_range.contains(&a.br().x);
+                    let no_going_up = velocity.0.y >= 0.;
+
+                    (on_top && corner_inside && no_going_up).then_some(Grounded {
+                        entity: ground,
+                        normal: Vec2::Y,
+                    })
+                }
+                (a, b) => {
+                    let no_going_up = velocity.0.y >= 0.;
+                    let normal = a.contact_normal(b, Vec2::NEG_Y * 0.5);
+
+                    (no_going_up && normal.y > WALKABLE_NORMAL).then_some(Grounded {
+                        entity: ground,
+                        normal,
+                    })
+                }
+            }
+        };
+
+        let static_ground = map_query
+            .iter()
+            .filter(|(_, map_layers)| filters.intersects(map_layers.memberships))
+            .flat_map(|(map, _)| map.nearby_objects(&position))
+            .map(|d| (d.entity, &d.collider));
+        // Other dynamic bodies, such as enemies or pushed crates, can be stood on as well.
+        let dynamic_ground = broadphase
+            .nearby_objects(&position)
+            .filter(|d| {
+                d.entity != entity
+                    && d.data
+                        .collision
+                        .is_some_and(|l| filters.intersects(l.memberships))
+            })
+            .map(|d| (d.entity, &d.collider));
+
+        match static_ground
+            .chain(dynamic_ground)
+            .find_map(|(ground, ground_collider)| ground_on(ground, ground_collider))
+        {
+            Some(ground) => commands.entity(entity).insert(ground),
+            None => commands.entity(entity).remove::<Grounded>(),
+        };
+    }
+}
+
+pub fn update_brushing(
+    mut commands: Commands,
+    map_query: Query<(&SpatialHash, &CollisionLayers)>,
     mut dynamic_bodies: Query<
-        (Entity, &GlobalTransform, &Collider, &Velocity),
-        (With<DynamicBody>, With<super::layers::CollidesWith<T>>),
+        (
+            Entity,
+            &GlobalTransform,
+            &Collider,
+            &Velocity,
+            &CollisionLayers,
+        ),
+        (With<DynamicBody>, Without<Sleeping>),
     >,
+    one_way: Query<(), With<OneWay>>,
+    physics_layers: Res<PhysicsLayers>,
 ) {
-    for (entity, global_transform, collider, velocity) in dynamic_bodies.iter_mut() {
-        let mut left = false;
-        let mut right = false;
+    for (entity, global_transform, collider, velocity, layers) in dynamic_bodies.iter_mut() {
+        let mut left = None;
+        let mut right = None;
+        let filters = layers.filters & physics_layers.brushing();
+
+        for (map, map_layers) in map_query.iter() {
+            if !filters.intersects(map_layers.memberships) {
+                continue;
+            }
 
-        for map in map_query.iter() {
             let collider = collider.global_absolute(global_transform);
             let nearby_colliders = map.nearby_objects(&collider.position());
 
             for spatial::SpatialData {
+                entity: static_entity,
                 collider: static_collider,
                 ..
             } in nearby_colliders
             {
+                // One way colliders have no walls to brush against.
+                if one_way.contains(*static_entity) {
+                    continue;
+                }
+
                 match (collider, static_collider) {
                     (AbsoluteCollider::Rect(a), AbsoluteCollider::Rect(b)) => {
                         let y_range = b.br().y..b.tl.y;
@@ -651,7 +1798,7 @@ pub fn update_brushing<T: Component>(
                         let no_going_right = velocity.0.x <= 0.;
 
                         if adjacent && corner_inside && no_going_right {
-                            left = true;
+                            left = Some(*static_entity);
                         }
 
                         // right
@@ -659,116 +1806,330 @@ pub fn update_brushing<T: Component>(
                         let no_going_left = velocity.0.x >= 0.;
 
                         if adjacent && corner_inside && no_going_left {
-                            right = true;
+                            right = Some(*static_entity);
                         }
                     }
-                    _ => {
-                        todo!("implement more grounded interactions")
+                    (a, b) => {
+                        if velocity.0.x <= 0. && a.contact_normal(&b, Vec2::NEG_X * 0.1).x > 0.7 {
+                            left = Some(*static_entity);
+                        }
+
+                        if velocity.0.x >= 0. && a.contact_normal(&b, Vec2::X * 0.1).x < -0.7 {
+                            right = Some(*static_entity);
+                        }
                     }
                 }
             }
         }
 
-        if left {
-            commands.entity(entity).insert(BrushingLeft);
-        } else {
-            commands.entity(entity).remove::<BrushingLeft>();
-        }
+        match left {
+            Some(wall) => commands.entity(entity).insert(BrushingLeft(wall)),
+            None => commands.entity(entity).remove::<BrushingLeft>(),
+        };
 
-        if right {
-            commands.entity(entity).insert(BrushingRight);
-        } else {
-            commands.entity(entity).remove::<BrushingRight>();
+        match right {
+            Some(wall) => commands.entity(entity).insert(BrushingRight(wall)),
+            None => commands.entity(entity).remove::<BrushingRight>(),
+        };
+    }
+}
+
+pub fn update_ceilinged(
+    mut commands: Commands,
+    map_query: Query<(&SpatialHash, &CollisionLayers)>,
+    dynamic_bodies: Query<
+        (
+            Entity,
+            &GlobalTransform,
+            &Collider,
+            &Velocity,
+            &CollisionLayers,
+        ),
+        (With<DynamicBody>, Without<Sleeping>),
+    >,
+    one_way: Query<(), With<OneWay>>,
+    physics_layers: Res<PhysicsLayers>,
+) {
+    for (entity, global_transform, collider, velocity, layers) in dynamic_bodies.iter() {
+        let mut ceiling = None;
+        let filters = layers.filters & physics_layers.ceiling();
+
+        // Bodies moving down have already left the ceiling.
+        if velocity.0.y >= 0. {
+            for (map, map_layers) in map_query.iter() {
+                if !filters.intersects(map_layers.memberships) {
+                    continue;
+                }
+
+                let collider = collider.global_absolute(global_transform);
+
+                for spatial::SpatialData {
+                    entity: static_entity,
+                    collider: static_collider,
+                    ..
+                } in map.nearby_objects(&collider.position())
+                {
+                    // One way colliders are passed through from below.
+                    if one_way.contains(*static_entity) {
+                        continue;
+                    }
+
+                    let touching = match (collider, static_collider) {
+                        (AbsoluteCollider::Rect(a), AbsoluteCollider::Rect(b)) => {
+                            let x_range = b.tl.x..b.br().x;
+
+                            let below = (a.tl.y - b.br().y).abs() < 0.5;
+                            let corner_inside =
+                                x_range.contains(&a.tl.x) || x_range.contains(&a.br().x);
+
+                            below && corner_inside
+                        }
+                        (a, b) => a.contact_normal(b, Vec2::Y * 0.5).y < -WALKABLE_NORMAL,
+                    };
+
+                    if touching {
+                        ceiling = Some(*static_entity);
+                        break;
+                    }
+                }
+            }
         }
+
+        match ceiling {
+            Some(ceiling) => commands.entity(entity).insert(Ceilinged(ceiling)),
+            None => commands.entity(entity).remove::<Ceilinged>(),
+        };
     }
 }
 
-pub fn handle_dynamic_body_collsions<T: Component>(
+/// The number of times [`handle_dynamic_body_collsions`] resolves every overlapping pair of
+/// bodies within a single step.
+const SOLVER_ITERATIONS: usize = 4;
+
+/// Separates overlapping [`DynamicBody`] entities from each other.
+///
+/// Two bodies are solved when either collides with the other's layers. The overlap is split
+/// between them by [`Mass`](super::velocity::Mass), and their velocities along the contact
+/// normal are exchanged according to their [`PhysicsMaterial`]s. [`Massive`] bodies and bodies
+/// resting against static geometry on the side they would be pushed towards are not moved.
+///
+/// [`Sleeping`] bodies are not moved either, nor are their velocities and contacts changed. Awake
+/// bodies are pushed out of them and wake them at the end of the tick.
+///
+/// Pairs are resolved in order of their entities so that the result does not depend on query
+/// iteration order.
+pub fn handle_dynamic_body_collsions(
+    mut broadphase: ResMut<spatial::DynamicBroadphase>,
     mut dynamic_bodies: Query<
         (
             Entity,
             &mut Transform,
+            &mut GlobalTransform,
             &Collider,
-            Option<&Massive>,
+            &mut Velocity,
             &mut TotalResolution,
+            &CollisionLayers,
+            &mut Collisions,
+            &super::velocity::Mass,
+            Option<&PhysicsMaterial>,
+            (
+                Has<Massive>,
+                Has<Grounded>,
+                Has<BrushingLeft>,
+                Has<BrushingRight>,
+            ),
+            Has<Sleeping>,
         ),
-        (With<DynamicBody>, With<super::layers::CollidesWith<T>>),
-    >,
-    other_bodies: Query<
-        (Entity, &Transform, &Collider, Option<&Massive>),
-        (
-            With<DynamicBody>,
-            With<T>,
-            Without<super::layers::CollidesWith<T>>,
-        ),
+        With<DynamicBody>,
     >,
+    mut stats: ResMut<PhysicsStats>,
 ) {
-    let mut other_bodies = other_bodies.iter().collect::<Vec<_>>();
-    other_bodies.sort_by_key(|(_, _, _, m)| {
-        if m.is_some() {
-            Ordering::Greater
-        } else {
-            Ordering::Less
+    let mut pairs = Vec::new();
+    for (entity, _, global_transform, collider, _, _, layers, .., sleeping) in dynamic_bodies.iter()
+    {
+        if sleeping {
+            continue;
         }
-    });
 
-    let mut spatial = spatial::SpatialHash::new(32.);
-
-    for (entity, transform, collider, massive) in other_bodies.iter() {
-        let absolute = collider.absolute(transform);
-        spatial.insert(spatial::SpatialData {
-            collider: absolute,
-            data: (massive.cloned(), *collider),
-            entity: *entity,
-        });
+        let collider = collider.global_absolute(global_transform);
+        pairs.extend(
+            broadphase
+                .objects_in_rect(&collider.get_aabb())
+                .filter(|d| d.entity != entity)
+                .filter(|d| {
+                    d.data.collision.is_some_and(|other| {
+                        layers.collides_with(&other) || other.collides_with(layers)
+                    })
+                })
+                .map(|d| (entity.min(d.entity), entity.max(d.entity))),
+        );
     }
+    pairs.sort_unstable();
+    pairs.dedup();
 
-    for (entity, mut transform, collider, massive, mut resolution) in dynamic_bodies.iter_mut() {
-        let original_collider = &collider;
-        let mut collider = collider.absolute(&transform);
-
-        let mut update_active = false;
-
-        // TODO: this shit is awful
-        //
-        // For some reason god forsaken, this will update twice even though the position in the hash is updated
-        // before the other, overlapping entity updates itself.
-        for spatial::SpatialData {
-            entity: se,
-            collider: sc,
-            ..
-        } in spatial.nearby_objects(&collider.position())
-        {
-            if collider.collides_with(sc) && massive.is_none() {
-                let res_v = collider.resolution(sc);
-                resolution.0 += res_v;
-                transform.translation += Vec3::new(res_v.x, res_v.y, 0.);
-                collider = original_collider.absolute(&transform);
-                update_active = true;
+    let mut moved = Vec::new();
+    for _ in 0..SOLVER_ITERATIONS {
+        let mut resolved = false;
+
+        for (a, b) in pairs.iter() {
+            let Ok([a, b]) = dynamic_bodies.get_many_mut([*a, *b]) else {
+                continue;
+            };
+
+            let (ea, mut ta, mut gta, ca, mut va, mut ra, la, mut cola, ma, mata, sa, za) = a;
+            let (eb, mut tb, mut gtb, cb, mut vb, mut rb, lb, mut colb, mb, matb, sb, zb) = b;
+
+            stats.pair_tests += 1;
+            let (abs_a, abs_b) = (ca.global_absolute(&gta), cb.global_absolute(&gtb));
+            if !abs_a.collides_with(&abs_b) {
+                continue;
             }
-        }
 
-        if update_active {
-            for spatial::SpatialData {
-                entity: se,
-                collider: sc,
-                ..
-            } in spatial.objects_in_cell_mut(&collider.position())
-            {
-                if *se == entity {
-                    *sc = collider;
-                    break;
+            // Pushes `a` out of `b`.
+            let res = abs_a.resolution(&abs_b);
+            let normal = res.normalize_or_zero();
+            if normal == Vec2::ZERO {
+                continue;
+            }
+
+            let inverse_a = if za {
+                0.
+            } else {
+                inverse_mass(ma.0, sa, normal)
+            };
+            let inverse_b = if zb {
+                0.
+            } else {
+                inverse_mass(mb.0, sb, -normal)
+            };
+            let total = inverse_a + inverse_b;
+            if total == 0. {
+                continue;
+            }
+
+            let relative_velocity = va.0 - vb.0;
+            if !za && cola.get(eb).is_none() {
+                cola.push(Contact {
+                    entity: eb,
+                    layers: lb.memberships,
+                    normal,
+                    penetration: res.length(),
+                    relative_velocity,
+                });
+            }
+            if !zb && colb.get(ea).is_none() {
+                colb.push(Contact {
+                    entity: ea,
+                    layers: la.memberships,
+                    normal: -normal,
+                    penetration: res.length(),
+                    relative_velocity: -relative_velocity,
+                });
+            }
+
+            for (entity, share, transform, global_transform, resolution) in [
+                (ea, res * inverse_a / total, &mut ta, &mut gta, &mut ra),
+                (eb, -res * inverse_b / total, &mut tb, &mut gtb, &mut rb),
+            ] {
+                if share == Vec2::ZERO {
+                    continue;
                 }
+
+                moved.push(entity);
+                resolution.0 += share;
+                transform.translation += share.extend(0.);
+                let mut global_t = global_transform.compute_transform();
+                global_t.translation += share.extend(0.);
+                **global_transform = GlobalTransform::from(global_t);
             }
+
+            let into = relative_velocity.dot(normal);
+            if into < 0. {
+                let restitution = mata
+                    .copied()
+                    .unwrap_or_default()
+                    .combine(&matb.copied().unwrap_or_default())
+                    .restitution;
+                let impulse = -(1. + restitution) * into / total;
+                // Only written when changed, so that sleeping bodies are not woken.
+                if inverse_a > 0. {
+                    va.0 += normal * impulse * inverse_a;
+                }
+                if inverse_b > 0. {
+                    vb.0 -= normal * impulse * inverse_b;
+                }
+            }
+
+            stats.resolutions += 1;
+            resolved = true;
+        }
+
+        if !resolved {
+            break;
         }
     }
+
+    moved.sort_unstable();
+    moved.dedup();
+    for (entity, _, global_transform, collider, ..) in dynamic_bodies.iter_many(&moved) {
+        if let Some(data) = broadphase.get(entity).map(|d| d.data) {
+            broadphase.update(spatial::SpatialData::from_entity(
+                entity,
+                global_transform,
+                collider,
+                data,
+            ));
+        }
+    }
+}
+
+/// The inverse mass of a body pushed along `direction`, zero when it cannot be moved that way.
+fn inverse_mass(
+    mass: f32,
+    (massive, grounded, left, right): (bool, bool, bool, bool),
+    direction: Vec2,
+) -> f32 {
+    let blocked = massive
+        || (grounded && direction.y < -WALKABLE_NORMAL)
+        || (left && direction.x < -WALKABLE_NORMAL)
+        || (right && direction.x > WALKABLE_NORMAL);
+
+    if blocked || mass <= 0. {
+        0.
+    } else {
+        mass.recip()
+    }
 }
 
 /// A marker component that indicates a tile should
 /// generate collision when inserted.
 #[derive(Debug, Default, Component)]
+#[component(on_remove = remove_tileset_collider)]
 pub struct TilesetCollider;
 
+/// The parents of [`TilesetCollider`] tiles removed since [`build_tile_set_colliders`] last ran.
+#[derive(Debug, Default, Resource)]
+pub struct RemovedTiles(Vec<Entity>);
+
+// Despawned tiles no longer know their parent by the time `RemovedComponents` reports them.
+fn remove_tileset_collider(mut world: DeferredWorld, entity: Entity, _: ComponentId) {
+    if let Some(parent) = world.get::<Parent>(entity).map(|p| p.get()) {
+        if let Some(mut removed) = world.get_resource_mut::<RemovedTiles>() {
+            removed.0.push(parent);
+        }
+    }
+}
+
+/// The shape of the collider generated by a [`TilesetCollider`] tile.
+///
+/// Square tiles are merged into larger rects, every other shape gets its own collider.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Component)]
+pub enum TileShape {
+    #[default]
+    Square,
+    Ramp(Ramp),
+}
+
 /// Sets the tile size, used primarily for collider construction.
 #[derive(Debug, Resource)]
 pub struct TilesetSize(pub f32);
@@ -779,198 +2140,501 @@ impl Default for TilesetSize {
     }
 }
 
+/// A [`StaticBody`] generated from the [`TilesetCollider`] tiles of its parent.
+///
+/// Rebuilt by [`build_tile_set_colliders`] whenever tiles of the parent are added or removed.
+#[derive(Debug, Default, Clone, Copy, Component)]
+#[require(StaticBody)]
+pub struct TileColliderBody;
+
+/// Builds [`TileColliderBody`] entities for every parent with new or removed
+/// [`TilesetCollider`] tiles.
+///
+/// Each parent, typically a level, is built independently. Its previous bodies are despawned
+/// first so that hot-reloaded levels do not leave stale colliders behind.
 pub fn build_tile_set_colliders(
     mut commands: Commands,
-    tiles: Query<(&Transform, &Parent), Added<TilesetCollider>>,
-    levels: Query<Entity>,
+    added: Query<&Parent, Added<TilesetCollider>>,
+    mut removed: ResMut<RemovedTiles>,
+    existing: Query<()>,
+    tiles: Query<
+        (
+            &Transform,
+            &Parent,
+            Has<OneWay>,
+            Option<&TileShape>,
+            Option<&PhysicsMaterial>,
+        ),
+        With<TilesetCollider>,
+    >,
+    bodies: Query<(Entity, &Parent), With<TileColliderBody>>,
     size: Res<TilesetSize>,
-    // manual_collision: Query<&Transform, Added<annual::Collision>>,
 ) {
-    //let mut num_colliders = 0;
+    let mut parents = added.iter().map(|p| p.get()).collect::<Vec<_>>();
+
+    // Only the parents of removed tiles are rebuilt. Parents that were despawned took their
+    // bodies with them.
+    parents.extend(removed.0.drain(..).filter(|p| existing.contains(*p)));
 
-    // ~14k without combining
-    // ~600 with horizontal combining
+    parents.sort();
+    parents.dedup();
 
-    if tiles.is_empty() {
+    if parents.is_empty() {
         return;
     }
 
-    // WARN: assumes that one level is loaded at a time!!
-    let level = tiles
-        .iter()
-        .next()
-        .map(|(_, p)| levels.get(p.get()).unwrap())
-        .unwrap();
-
-    let mut parents = HashSet::<Entity>::default();
-    for (_, parent) in tiles.iter() {
-        parents.insert(parent.get());
+    for (entity, parent) in bodies.iter() {
+        if parents.contains(&parent.get()) {
+            commands.entity(entity).despawn_recursive();
+        }
     }
 
     let tile_size = size.0;
-    let offset = tile_size / 2.;
 
     for parent in parents.into_iter() {
-        let cached_collider_positions = tiles
-            .iter()
-            .filter(|(_, p)| p.get() == parent)
-            .map(|(t, _)| Vec2::new(t.translation.x + offset, t.translation.y + offset))
-            .collect::<Vec<_>>();
+        // one way tiles and tiles of different materials are merged separately so they never
+        // combine with each other.
+        let mut groups = Vec::<(bool, Option<PhysicsMaterial>)>::new();
+        let mut squares = Vec::<Vec<Vec2>>::new();
+
+        for (transform, _, one_way, shape, material) in
+            tiles.iter().filter(|(_, p, ..)| p.get() == parent)
+        {
+            let position = transform.translation.xy();
 
-        if cached_collider_positions.is_empty() {
-            return;
+            if let Some(TileShape::Ramp(ramp)) = shape {
+                let mut body = commands.spawn((
+                    Transform::from_translation(position.extend(0.)),
+                    TileColliderBody,
+                    Collider::from_ramp(Vec2::ZERO, Vec2::splat(tile_size), *ramp),
+                ));
+                if one_way {
+                    body.insert(OneWay);
+                }
+                if let Some(material) = material {
+                    body.insert(*material);
+                }
+                body.set_parent(parent);
+                continue;
+            }
+
+            let group = (one_way, material.copied());
+            match groups.iter().position(|g| *g == group) {
+                Some(i) => squares[i].push(position),
+                None => {
+                    groups.push(group);
+                    squares.push(vec![position]);
+                }
+            }
         }
 
         commands.entity(parent).with_children(|level| {
-            for (pos, collider) in
-                build_colliders_from_vec2(cached_collider_positions, tile_size).into_iter()
-            {
-                level.spawn((
-                    Transform::from_translation((pos - Vec2::splat(tile_size / 2.)).extend(0.)),
-                    StaticBody,
-                    collider,
-                ));
-                //num_colliders += 1;
+            for ((one_way, material), positions) in groups.into_iter().zip(squares) {
+                for (position, collider) in merge_tiles(positions, tile_size) {
+                    let mut body = level.spawn((
+                        Transform::from_translation(position.extend(0.)),
+                        TileColliderBody,
+                        collider,
+                    ));
+
+                    if one_way {
+                        body.insert(OneWay);
+                    }
+                    if let Some(material) = material {
+                        body.insert(material);
+                    }
+                }
             }
         });
     }
-
-    //println!("num_colliders: {num_colliders}");
 }
 
-fn build_colliders_from_vec2(mut positions: Vec<Vec2>, tile_size: f32) -> Vec<(Vec2, Collider)> {
-    positions.sort_by(|a, b| {
-        let y_cmp = a.y.partial_cmp(&b.y).unwrap_or(std::cmp::Ordering::Equal);
-        if y_cmp == std::cmp::Ordering::Equal {
-            a.x.partial_cmp(&b.x).unwrap_or(std::cmp::Ordering::Equal)
-        } else {
-            y_cmp
-        }
-    });
+/// Greedily merges square tiles into as few rects as possible.
+///
+/// Starting from the top left, each unmerged tile grows into a run to the right, which then
+/// grows downwards while every tile below the run is free. Returns the translation of the top
+/// left tile of each rect along with its collider.
+fn merge_tiles(positions: Vec<Vec2>, tile_size: f32) -> Vec<(Vec2, Collider)> {
+    let cell = |position: Vec2| {
+        (
+            (position.x / tile_size).round() as i32,
+            (position.y / tile_size).round() as i32,
+        )
+    };
 
-    let mut rows = Vec::with_capacity(positions.len() / 2);
-    let mut current_y = None;
-    let mut current_xs = Vec::with_capacity(positions.len() / 2);
-    for v in positions.into_iter() {
-        match current_y {
-            None => {
-                current_y = Some(v.y);
-                current_xs.push(v.x);
-            }
-            Some(y) => {
-                if v.y == y {
-                    current_xs.push(v.x);
-                } else {
-                    rows.push((y, current_xs.clone()));
-                    current_xs.clear();
+    let mut free = positions
+        .iter()
+        .map(|p| (cell(*p), *p))
+        .collect::<HashMap<_, _>>();
 
-                    current_y = Some(v.y);
-                    current_xs.push(v.x);
-                }
+    let mut cells = free.keys().copied().collect::<Vec<_>>();
+    cells.sort_by_key(|(x, y)| (-y, *x));
+
+    let mut colliders = Vec::new();
+    for (x, y) in cells.into_iter() {
+        let Some(origin) = free.remove(&(x, y)) else {
+            continue;
+        };
+
+        let mut width = 1;
+        while free.remove(&(x + width, y)).is_some() {
+            width += 1;
+        }
+
+        let mut height = 1;
+        while (x..x + width).all(|x| free.contains_key(&(x, y - height))) {
+            for x in x..x + width {
+                free.remove(&(x, y - height));
             }
+            height += 1;
         }
+
+        colliders.push((
+            origin,
+            Collider::from_rect(
+                Vec2::ZERO,
+                Vec2::new(width as f32, height as f32) * tile_size,
+            ),
+        ));
     }
 
-    match current_y {
-        Some(y) => {
-            rows.push((y, current_xs));
-        }
-        None => unreachable!(),
+    colliders
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tiles(cells: &[(i32, i32)]) -> Vec<Vec2> {
+        cells
+            .iter()
+            .map(|(x, y)| Vec2::new(*x as f32, *y as f32) * 16.)
+            .collect()
     }
 
-    #[derive(Debug, Clone, Copy)]
-    struct Plate {
-        y_start: f32,
-        y_end: f32,
-        x_start: f32,
-        x_end: f32,
+    fn sizes(colliders: &[(Vec2, Collider)]) -> Vec<(Vec2, Vec2)> {
+        colliders
+            .iter()
+            .map(|(origin, collider)| match collider {
+                Collider::Rect(rect) => (*origin, rect.size),
+                _ => panic!("tiles are merged into rects"),
+            })
+            .collect()
     }
 
-    let mut row_plates = Vec::with_capacity(rows.len());
-    for (y, row) in rows.into_iter() {
-        let mut current_x = None;
-        let mut x_start = None;
-        let mut plates = Vec::with_capacity(row.len() / 4);
+    #[test]
+    fn merge_tiles_block() {
+        let merged = merge_tiles(
+            tiles(&[(0, 0), (1, 0), (2, 0), (0, -1), (1, -1), (2, -1)]),
+            16.,
+        );
 
-        for x in row.iter() {
-            match (current_x, x_start) {
-                (None, None) => {
-                    current_x = Some(*x);
-                    x_start = Some(*x);
-                }
-                (Some(cx), Some(xs)) => {
-                    if *x > cx + tile_size {
-                        plates.push(Plate {
-                            x_end: cx + tile_size,
-                            x_start: xs,
-                            y_start: y - tile_size,
-                            y_end: y,
-                        });
-                        x_start = Some(*x);
-                    }
+        assert_eq!(sizes(&merged), [(Vec2::ZERO, Vec2::new(48., 32.))]);
+    }
 
-                    current_x = Some(*x);
-                }
-                _ => unreachable!(),
-            }
+    #[test]
+    fn merge_tiles_covers_every_tile_once() {
+        // An L shape, a lone tile and a duplicate.
+        let cells = [(0, 0), (0, -1), (0, -2), (1, -2), (2, -2), (5, 5), (5, 5)];
+        let merged = sizes(&merge_tiles(tiles(&cells), 16.));
+
+        let area = merged.iter().map(|(_, size)| size.x * size.y).sum::<f32>();
+        assert_eq!(area, 6. * 16. * 16.);
+        for (x, y) in cells {
+            let center = Vec2::new(x as f32 * 16. + 8., y as f32 * 16. - 8.);
+            let covering = merged
+                .iter()
+                .filter(|(origin, size)| {
+                    RectCollider {
+                        tl: *origin,
+                        size: *size,
+                    }
+                    .contains(&center)
+                })
+                .count();
+            assert_eq!(covering, 1, "tile {x}, {y}");
         }
+    }
 
-        match (current_x, x_start) {
-            (Some(cx), Some(xs)) => {
-                plates.push(Plate {
-                    x_end: cx + tile_size,
-                    x_start: xs,
-                    y_start: y - tile_size,
-                    y_end: y,
-                });
-            }
-            _ => unreachable!(),
-        }
+    #[test]
+    fn merge_tiles_keeps_gaps() {
+        let merged = merge_tiles(tiles(&[(0, 0), (2, 0)]), 16.);
 
-        row_plates.push(plates);
+        assert_eq!(
+            sizes(&merged),
+            [
+                (Vec2::ZERO, Vec2::splat(16.)),
+                (Vec2::new(32., 0.), Vec2::splat(16.))
+            ]
+        );
     }
 
-    let mut output = HashMap::<(i32, i32), Vec<Plate>>::default();
-    for plates in row_plates.iter() {
-        for plate in plates.iter() {
-            let entry = output
-                .entry((plate.x_start as i32, plate.x_end as i32))
-                .or_default();
-            entry.push(*plate);
-        }
+    #[test]
+    fn one_way_platforms_only_stop_falling_bodies() {
+        use bevy::ecs::system::RunSystemOnce;
+        use std::time::Duration;
+
+        let mut world = World::new();
+        world.init_resource::<PhysicsStats>();
+        let mut time = Time::<()>::default();
+        time.advance_by(Duration::from_secs_f32(1. / 60.));
+        world.insert_resource(time);
+        super::super::time::spawn_time_scales(&mut world);
+
+        let walls = CollisionLayers::new(LayerMask(1), LayerMask::NONE);
+        let map = world.spawn((SpatialHash::<()>::new(16.), walls)).id();
+        world
+            .spawn((
+                StaticBody,
+                OneWay,
+                Collider::from_rect(Vec2::ZERO, Vec2::new(32., 4.)),
+                GlobalTransform::default(),
+            ))
+            .set_parent(map);
+        world
+            .run_system_once(spatial::store_static_body_in_spatial_map)
+            .unwrap();
+
+        // Sunk 1 unit into the top of the platform.
+        let body = |velocity| {
+            (
+                Collider::from_rect(Vec2::new(0., 7.), Vec2::splat(8.)),
+                Transform::default(),
+                GlobalTransform::default(),
+                Velocity(Vec2::new(0., velocity)),
+                DynamicBody,
+                CollisionLayers::new(LayerMask::NONE, LayerMask(1)),
+            )
+        };
+        let falling = world.spawn(body(-60.)).id();
+        let rising = world.spawn(body(60.)).id();
+        let dropping = world.spawn((body(-60.), DropThrough)).id();
+        world.run_system_once(handle_collisions).unwrap();
+
+        let y = |world: &World, entity| world.get::<Transform>(entity).unwrap().translation.y;
+        assert_eq!(y(&world, falling), 1.);
+        assert_eq!(y(&world, rising), 0.);
+        assert_eq!(y(&world, dropping), 0.);
+
+        // Kept while falling through, removed once clear of the platform.
+        world.run_system_once(clear_drop_through).unwrap();
+        assert!(world.get::<DropThrough>(dropping).is_some());
+        *world.get_mut::<GlobalTransform>(dropping).unwrap() =
+            GlobalTransform::from_xyz(0., -16., 0.);
+        world.run_system_once(clear_drop_through).unwrap();
+        assert!(world.get::<DropThrough>(dropping).is_none());
     }
 
-    for (_, plates) in output.iter_mut() {
-        let mut new_plates = Vec::with_capacity(plates.len());
+    #[test]
+    fn continuous_collision_stops_at_thin_walls() {
+        use bevy::ecs::system::RunSystemOnce;
+
+        let mut world = World::new();
+        world.init_resource::<PhysicsStats>();
+
+        let walls = CollisionLayers::new(LayerMask(1), LayerMask::NONE);
+        let map = world.spawn((SpatialHash::<()>::new(16.), walls)).id();
+        let wall = world
+            .spawn((
+                StaticBody,
+                Collider::from_rect(Vec2::new(32., 64.), Vec2::new(2., 128.)),
+                GlobalTransform::default(),
+            ))
+            .set_parent(map)
+            .id();
+        world
+            .run_system_once(spatial::store_static_body_in_spatial_map)
+            .unwrap();
+
+        // Moved past the wall in a single step.
+        let end = Transform::from_xyz(100., 0., 0.);
+        let body = world
+            .spawn((
+                Collider::from_rect(Vec2::new(0., 8.), Vec2::splat(8.)),
+                end,
+                GlobalTransform::from(end),
+                Velocity(Vec2::new(6000., 0.)),
+                ContinuousCollision {
+                    motion: Vec2::new(100., 0.),
+                },
+                DynamicBody,
+                CollisionLayers::new(LayerMask::NONE, LayerMask(1)),
+            ))
+            .id();
+        world.run_system_once(handle_continuous_collisions).unwrap();
+
+        assert_eq!(world.get::<Transform>(body).unwrap().translation.x, 24.);
+        assert_eq!(world.get::<Velocity>(body).unwrap().0.x, 0.);
+        let contact = world.get::<Collisions>(body).unwrap().get(wall).copied();
+        assert_eq!(contact.map(|c| c.normal), Some(Vec2::NEG_X));
+    }
 
-        while let Some(mut plate) = plates.pop() {
-            while let Some(next_plate) = plates.pop() {
-                if next_plate.y_end == plate.y_start {
-                    let end = plate.y_end;
-                    plate = next_plate;
-                    plate.y_end = end;
-                } else {
-                    new_plates.push(next_plate);
-                    break;
-                }
-            }
+    #[test]
+    fn grounded_on_dynamic_body() {
+        use super::super::layers::{CollidesWith, RegisterPhysicsLayer, Wall};
+        use bevy::ecs::system::RunSystemOnce;
+
+        let mut app = App::new();
+        app.init_resource::<spatial::DynamicBroadphase>()
+            .init_resource::<PhysicsStats>()
+            .register_collision_layer::<Wall>()
+            .register_grounded_layer::<Wall>();
+
+        let body = |tl| {
+            (
+                Collider::from_rect(tl, Vec2::splat(16.)),
+                GlobalTransform::default(),
+                Velocity::default(),
+                DynamicBody,
+                Wall,
+                CollidesWith::<Wall>::default(),
+            )
+        };
+        let world = app.world_mut();
+        let top = world.spawn(body(Vec2::new(4., 16.))).id();
+        let bottom = world.spawn(body(Vec2::ZERO)).id();
+        world
+            .run_system_once(spatial::update_dynamic_broadphase)
+            .unwrap();
+        world.run_system_once(update_grounded).unwrap();
+
+        assert_eq!(world.get::<Grounded>(top).map(|g| g.entity), Some(bottom));
+        assert!(world.get::<Grounded>(bottom).is_none());
+    }
+
+    #[test]
+    fn sleeping_bodies_are_not_pushed() {
+        use super::super::layers::{CollidesWith, RegisterPhysicsLayer, Wall};
+        use bevy::ecs::system::RunSystemOnce;
+
+        let mut app = App::new();
+        app.init_resource::<spatial::DynamicBroadphase>()
+            .init_resource::<PhysicsStats>()
+            .register_collision_layer::<Wall>();
+
+        let body = |x| {
+            (
+                Collider::from_rect(Vec2::new(x, 16.), Vec2::splat(16.)),
+                Transform::default(),
+                GlobalTransform::default(),
+                Velocity(Vec2::new(-x, 0.)),
+                super::super::velocity::Mass::default(),
+                DynamicBody,
+                Wall,
+                CollidesWith::<Wall>::default(),
+            )
+        };
+        let world = app.world_mut();
+        let sleeping = world.spawn((body(0.), Sleeping::Resting)).id();
+        let awake = world.spawn(body(8.)).id();
+        world
+            .run_system_once(spatial::update_dynamic_broadphase)
+            .unwrap();
+        world
+            .run_system_once(handle_dynamic_body_collsions)
+            .unwrap();
+
+        let translation = |entity| world.get::<Transform>(entity).unwrap().translation;
+        assert_eq!(translation(sleeping), Vec3::ZERO);
+        assert_eq!(translation(awake), Vec3::new(8., 0., 0.));
+        assert_eq!(world.get::<Velocity>(sleeping).unwrap().0, Vec2::ZERO);
+        assert!(world
+            .get::<Collisions>(sleeping)
+            .unwrap()
+            .contacts()
+            .is_empty());
+        assert!(world
+            .get::<Collisions>(awake)
+            .unwrap()
+            .get(sleeping)
+            .is_some());
+    }
+
+    #[test]
+    fn resting_bodies_wake_when_their_support_is_removed() {
+        use super::super::{sleep, trigger::TriggerEnter};
+        use bevy::ecs::event::Events;
 
-            new_plates.push(plate);
+        let mut world = World::new();
+        world.insert_resource(sleep::SleepConfig {
+            rest_ticks: 2,
+            ..Default::default()
+        });
+        world.init_resource::<spatial::DynamicBroadphase>();
+        world.init_resource::<Events<TriggerEnter>>();
+        // A registered system, so only velocity changes since its last run wake the body.
+        let update = world.register_system(sleep::update_sleeping);
+
+        let ground = world.spawn(GlobalTransform::default()).id();
+        let body = world
+            .spawn((
+                Collider::from_rect(Vec2::new(0., 16.), Vec2::splat(16.)),
+                GlobalTransform::default(),
+                Velocity::default(),
+                DynamicBody,
+                Grounded {
+                    entity: ground,
+                    normal: Vec2::Y,
+                },
+            ))
+            .id();
+
+        for _ in 0..3 {
+            world.run_system(update).unwrap();
         }
+        assert_eq!(world.get::<Sleeping>(body), Some(&Sleeping::Resting));
 
-        *plates = new_plates;
+        world.despawn(ground);
+        world.run_system(update).unwrap();
+        assert_eq!(world.get::<Sleeping>(body), None);
     }
 
-    let mut colliders = Vec::new();
-    for plate in output.into_values().flatten() {
-        colliders.push((
-            Vec2::new(plate.x_start, plate.y_end),
-            Collider::from_rect(
-                Vec2::ZERO,
-                Vec2::new(plate.x_end - plate.x_start, plate.y_end - plate.y_start),
-            ),
-        ));
+    #[test]
+    fn removing_tiles_rebuilds_only_their_level() {
+        let mut world = World::new();
+        world.init_resource::<TilesetSize>();
+        world.init_resource::<RemovedTiles>();
+        // A registered system, so `Added` only reports tiles spawned since its last run.
+        let build = world.register_system(build_tile_set_colliders);
+        let levels = [world.spawn_empty().id(), world.spawn_empty().id()];
+        let mut tiles = Vec::new();
+        for level in levels {
+            for x in 0..2 {
+                let transform = Transform::from_xyz(x as f32 * 16., 0., 0.);
+                tiles.push(
+                    world
+                        .spawn((transform, TilesetCollider))
+                        .set_parent(level)
+                        .id(),
+                );
+            }
+        }
+        world.run_system(build).unwrap();
+
+        let bodies = |world: &mut World| {
+            let mut bodies = world
+                .query_filtered::<(Entity, &Parent), With<TileColliderBody>>()
+                .iter(world)
+                .map(|(e, p)| (p.get(), e))
+                .collect::<Vec<_>>();
+            bodies.sort();
+            bodies
+        };
+        let before = bodies(&mut world);
+
+        world.entity_mut(tiles[0]).despawn_recursive();
+        world.run_system(build).unwrap();
+        let after = bodies(&mut world);
+
+        let of = |bodies: &[(Entity, Entity)], level| {
+            bodies
+                .iter()
+                .filter(|(p, _)| *p == level)
+                .map(|(_, e)| *e)
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(of(&before, levels[1]), of(&after, levels[1]));
+        assert_ne!(of(&before, levels[0]), of(&after, levels[0]));
+        assert_eq!(of(&after, levels[0]).len(), 1);
     }
-
-    colliders
 }
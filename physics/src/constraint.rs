@@ -0,0 +1,213 @@
+use super::{
+    collision::{Collider, DynamicBody, Massive},
+    sleep::Sleeping,
+    velocity::{Mass, Velocity},
+};
+use bevy::prelude::*;
+
+/// The other end of a [`DistanceConstraint`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ConstraintAnchor {
+    /// The center of an entity's [`Collider`], or its translation without one.
+    ///
+    /// Only [`DynamicBody`] anchors are moved by the constraint.
+    Entity(Entity),
+    /// A fixed point in the world.
+    Point(Vec2),
+}
+
+/// How a [`DistanceConstraint`] holds its body.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum ConstraintKind {
+    /// Only pulls the body back when it is further than the constraint's length, like a rope.
+    #[default]
+    Rope,
+    /// Keeps the body at exactly the constraint's length, like a rigid rod.
+    Rod,
+}
+
+/// Keeps the collider center of this [`DynamicBody`] within `length` of its anchor.
+///
+/// Solved every [`PhysicsSubstep`](super::PhysicsSubstep) before collisions, which always win
+/// over the constraint. Removed when an entity anchor is despawned.
+#[derive(Debug, Clone, Copy, Component)]
+pub struct DistanceConstraint {
+    pub anchor: ConstraintAnchor,
+    pub length: f32,
+    pub kind: ConstraintKind,
+    /// The fraction of the distance error corrected every step, `1.` being fully rigid.
+    pub stiffness: f32,
+    ends: Option<(Vec2, Vec2)>,
+}
+
+impl DistanceConstraint {
+    pub fn rope(anchor: ConstraintAnchor, length: f32) -> Self {
+        Self {
+            anchor,
+            length,
+            kind: ConstraintKind::Rope,
+            stiffness: 1.,
+            ends: None,
+        }
+    }
+
+    pub fn rod(anchor: ConstraintAnchor, length: f32) -> Self {
+        Self {
+            kind: ConstraintKind::Rod,
+            ..Self::rope(anchor, length)
+        }
+    }
+
+    pub fn with_stiffness(mut self, stiffness: f32) -> Self {
+        self.stiffness = stiffness;
+        self
+    }
+
+    /// The body and anchor positions after the constraint was last solved.
+    pub fn ends(&self) -> Option<(Vec2, Vec2)> {
+        self.ends
+    }
+
+    /// `count` points evenly spaced from the body to the anchor, starting at the body.
+    ///
+    /// Used to place the links of a chain or rope along the constraint.
+    pub fn points(&self, count: usize) -> impl Iterator<Item = Vec2> + '_ {
+        self.ends.into_iter().flat_map(move |(body, anchor)| {
+            (0..count).map(move |i| body.lerp(anchor, i as f32 / count as f32))
+        })
+    }
+}
+
+type ConstraintBodies<'w, 's> = Query<
+    'w,
+    's,
+    (
+        &'static mut Transform,
+        &'static mut GlobalTransform,
+        Option<&'static Collider>,
+        Option<&'static mut Velocity>,
+        &'static Mass,
+        Has<Massive>,
+        Has<DynamicBody>,
+    ),
+>;
+
+pub(crate) fn solve_distance_constraints(
+    mut commands: Commands,
+    mut constraints: Query<(Entity, &mut DistanceConstraint), Without<Sleeping>>,
+    mut bodies: ConstraintBodies,
+    anchors: Query<(&GlobalTransform, Option<&Collider>), Without<Mass>>,
+) {
+    let mut order = constraints.iter().map(|(e, _)| e).collect::<Vec<_>>();
+    order.sort_unstable();
+
+    for entity in order {
+        let Ok((_, mut constraint)) = constraints.get_mut(entity) else {
+            continue;
+        };
+
+        let Some(body) = end(&bodies, entity) else {
+            continue;
+        };
+        let (anchor, anchor_body) = match constraint.anchor {
+            ConstraintAnchor::Point(point) => (End::fixed(point), None),
+            ConstraintAnchor::Entity(anchor) => match end(&bodies, anchor) {
+                Some(end) => (end, Some(anchor)),
+                None => match anchors.get(anchor) {
+                    Ok((global_transform, collider)) => {
+                        (End::fixed(center(global_transform, collider)), None)
+                    }
+                    Err(_) => {
+                        commands.entity(entity).remove::<DistanceConstraint>();
+                        continue;
+                    }
+                },
+            },
+        };
+
+        let delta = body.position - anchor.position;
+        let error = delta.length() - constraint.length;
+        let normal = delta.normalize_or_zero();
+        let total = body.inverse_mass + anchor.inverse_mass;
+        let slack = constraint.kind == ConstraintKind::Rope && error <= 0.;
+        if normal == Vec2::ZERO || slack || total == 0. {
+            constraint.ends = Some((body.position, anchor.position));
+            continue;
+        }
+
+        let correction = normal * error * constraint.stiffness.clamp(0., 1.) / total;
+        // Only velocity which further stretches or compresses the constraint is removed.
+        let separating = (body.velocity - anchor.velocity).dot(normal);
+        let impulse = if separating * error > 0. {
+            separating / total
+        } else {
+            0.
+        };
+
+        let mut ends = (body.position, anchor.position);
+        for (target, end, inverse, sign) in [
+            (Some(entity), &mut ends.0, body.inverse_mass, -1.),
+            (anchor_body, &mut ends.1, anchor.inverse_mass, 1.),
+        ] {
+            let Some(target) = target.filter(|_| inverse > 0.) else {
+                continue;
+            };
+            let Ok((mut transform, mut global_transform, _, velocity, ..)) = bodies.get_mut(target)
+            else {
+                continue;
+            };
+
+            let offset = correction * inverse * sign;
+            *end += offset;
+            transform.translation += offset.extend(0.);
+            let mut global_t = global_transform.compute_transform();
+            global_t.translation += offset.extend(0.);
+            *global_transform = GlobalTransform::from(global_t);
+
+            if let Some(mut velocity) = velocity {
+                velocity.0 += normal * impulse * inverse * sign;
+            }
+        }
+
+        constraint.ends = Some(ends);
+    }
+}
+
+/// The state of one end of a [`DistanceConstraint`] before it is solved.
+struct End {
+    position: Vec2,
+    velocity: Vec2,
+    inverse_mass: f32,
+}
+
+impl End {
+    fn fixed(position: Vec2) -> Self {
+        Self {
+            position,
+            velocity: Vec2::ZERO,
+            inverse_mass: 0.,
+        }
+    }
+}
+
+fn end(bodies: &ConstraintBodies, entity: Entity) -> Option<End> {
+    let (_, global_transform, collider, velocity, mass, massive, dynamic) =
+        bodies.get(entity).ok()?;
+
+    Some(End {
+        position: center(global_transform, collider),
+        velocity: velocity.map(|v| v.0).unwrap_or_default(),
+        inverse_mass: if !dynamic || massive || mass.0 <= 0. {
+            0.
+        } else {
+            mass.0.recip()
+        },
+    })
+}
+
+fn center(global_transform: &GlobalTransform, collider: Option<&Collider>) -> Vec2 {
+    match collider {
+        Some(collider) => collider.global_absolute(global_transform).center(),
+        None => global_transform.translation().xy(),
+    }
+}
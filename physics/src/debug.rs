@@ -1,18 +1,89 @@
-use super::collision::{Collider, CollidesWith, DynamicBody, StaticBody};
-use super::trigger::{Trigger, TriggerEvent};
+use super::collision::{Collider, CollidesWith, Collisions, DynamicBody, StaticBody};
+use super::constraint::{ConstraintKind, DistanceConstraint};
+use super::gravity::{BrushingLeft, BrushingRight, Ceilinged, Grounded};
+use super::spatial::SpatialHash;
+use super::trigger::{Trigger, TriggerTargets};
+use super::velocity::{Acceleration, Velocity};
 use bevy::input::keyboard::KeyboardInput;
 use bevy::input::ButtonState;
 use bevy::prelude::*;
+use bevy::render::mesh::{Indices, PrimitiveTopology};
+use bevy::render::render_asset::RenderAssetUsages;
 use bevy::sprite::{Wireframe2d, Wireframe2dColor};
 use bevy_pixel_gfx::pixel_perfect::HIGH_RES_LAYER;
-use rand::Rng;
 
-#[derive(Resource)]
-pub struct ShowCollision(pub bool);
+/// Toggles the layers of the physics debug overlay.
+#[derive(Debug, Clone, Resource)]
+pub struct PhysicsDebugConfig {
+    /// Shows the overlay, toggled by `toggle_key`.
+    pub enabled: bool,
+    /// The key toggling `enabled`, if any.
+    pub toggle_key: Option<KeyCode>,
+    /// Collider and trigger wireframes, red while colliding and green while triggered.
+    pub colliders: bool,
+    /// Occupied cells of every [`SpatialHash`], more opaque the more objects they hold.
+    pub hash_cells: bool,
+    /// [`Velocity`] and [`Acceleration`] vectors of dynamic bodies.
+    pub vectors: bool,
+    /// The normal and penetration of every contact in [`Collisions`].
+    pub contacts: bool,
+    /// The sides of bodies which are [`Grounded`], [`BrushingLeft`], [`BrushingRight`] or
+    /// [`Ceilinged`].
+    pub body_states: bool,
+    /// Lines from each [`Trigger`] to the entities inside of it.
+    pub trigger_overlaps: bool,
+    /// Every [`DistanceConstraint`], yellow while slack.
+    pub constraints: bool,
+}
+
+impl Default for PhysicsDebugConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            toggle_key: Some(KeyCode::KeyP),
+            colliders: true,
+            hash_cells: false,
+            vectors: false,
+            contacts: false,
+            body_states: false,
+            trigger_overlaps: false,
+            constraints: false,
+        }
+    }
+}
+
+impl PhysicsDebugConfig {
+    fn shows(&self, layer: impl Fn(&Self) -> bool) -> bool {
+        self.enabled && layer(self)
+    }
+}
+
+/// Draws the physics debug overlay on the high resolution layer.
+#[derive(Default, Reflect, GizmoConfigGroup)]
+pub struct PhysicsGizmos;
+
+pub(crate) fn gizmo_config() -> GizmoConfig {
+    GizmoConfig {
+        render_layers: HIGH_RES_LAYER,
+        ..Default::default()
+    }
+}
+
+/// Run condition for the overlay layer selected by `layer`.
+pub(crate) fn debug_layer(
+    layer: impl Fn(&PhysicsDebugConfig) -> bool,
+) -> impl Fn(Res<PhysicsDebugConfig>) -> bool {
+    move |config| config.shows(&layer)
+}
+
+/// Wireframes of triggers are drawn above those of colliders.
+const COLLIDER_Z: f32 = 998.;
+const TRIGGER_Z: f32 = 999.;
 
 impl Collider {
     fn debug_wireframe_bundle(
         &self,
+        z: f32,
         meshes: &mut Assets<Mesh>,
         materials: &mut Assets<ColorMaterial>,
     ) -> impl Bundle {
@@ -22,18 +93,20 @@ impl Collider {
                     Mesh2d(meshes.add(Rectangle::new(rect.size.x, rect.size.y))),
                     MeshMaterial2d(materials.add(Color::NONE)),
                     Transform::from_xyz(
-                        // 0., 0.,
                         rect.tl.x + rect.size.x / 2.,
                         rect.tl.y - rect.size.y / 2.,
-                        // rect.tl.x,
-                        // rect.tl.y,
-                        rand::thread_rng().gen_range(500.0..999.0),
+                        z,
                     ),
                 ),
                 Self::Circle(circle) => (
                     Mesh2d(meshes.add(Circle::new(circle.radius))),
                     MeshMaterial2d(materials.add(Color::NONE)),
-                    Transform::from_xyz(circle.position.x, circle.position.y, 999.),
+                    Transform::from_xyz(circle.position.x, circle.position.y, z),
+                ),
+                Self::Polygon(polygon) => (
+                    Mesh2d(meshes.add(polygon_mesh(polygon.points()))),
+                    MeshMaterial2d(materials.add(Color::NONE)),
+                    Transform::from_xyz(0., 0., z),
                 ),
             },
             HIGH_RES_LAYER,
@@ -45,6 +118,22 @@ impl Collider {
     }
 }
 
+fn polygon_mesh(points: &[Vec2]) -> Mesh {
+    let positions = points.iter().map(|p| [p.x, p.y, 0.]).collect::<Vec<_>>();
+    let indices = (1..points.len() as u32 - 1)
+        .flat_map(|i| [0, i, i + 1])
+        .collect::<Vec<_>>();
+
+    Mesh::new(
+        PrimitiveTopology::TriangleList,
+        RenderAssetUsages::default(),
+    )
+    .with_inserted_attribute(Mesh::ATTRIBUTE_NORMAL, vec![[0., 0., 1.]; points.len()])
+    .with_inserted_attribute(Mesh::ATTRIBUTE_UV_0, vec![[0., 0.]; points.len()])
+    .with_inserted_attribute(Mesh::ATTRIBUTE_POSITION, positions)
+    .with_inserted_indices(Indices::U32(indices))
+}
+
 #[derive(Component)]
 pub struct DebugWireframe;
 
@@ -53,19 +142,15 @@ pub struct Marked;
 
 pub fn update_show_collision(
     mut reader: EventReader<KeyboardInput>,
-    mut show: ResMut<ShowCollision>,
+    mut config: ResMut<PhysicsDebugConfig>,
 ) {
+    let Some(toggle_key) = config.toggle_key else {
+        return;
+    };
+
     for event in reader.read() {
-        if matches!(
-            event,
-            KeyboardInput {
-                key_code: KeyCode::KeyP,
-                state: ButtonState::Pressed,
-                repeat: false,
-                ..
-            }
-        ) {
-            show.0 = !show.0;
+        if event.key_code == toggle_key && event.state == ButtonState::Pressed && !event.repeat {
+            config.enabled = !config.enabled;
         }
     }
 }
@@ -78,17 +163,22 @@ pub fn debug_display_collider_wireframe(
     mut commands: Commands,
     mut meshes: ResMut<Assets<Mesh>>,
     mut materials: ResMut<Assets<ColorMaterial>>,
-    show: Res<ShowCollision>,
+    config: Res<PhysicsDebugConfig>,
 ) {
-    if show.0 {
-        for (entity, collider) in naked_colliders
+    if config.shows(|c| c.colliders) {
+        for (entity, collider, z) in naked_colliders
             .iter()
-            .chain(naked_trigger_colliders.iter().map(|(e, t)| (e, &t.0)))
+            .map(|(e, c)| (e, c, COLLIDER_Z))
+            .chain(
+                naked_trigger_colliders
+                    .iter()
+                    .map(|(e, t)| (e, &t.0, TRIGGER_Z)),
+            )
         {
             let wireframe = commands
                 .spawn((
                     DebugWireframe,
-                    collider.debug_wireframe_bundle(&mut meshes, &mut materials),
+                    collider.debug_wireframe_bundle(z, &mut meshes, &mut materials),
                 ))
                 .id();
             commands
@@ -108,16 +198,19 @@ pub fn debug_display_collider_wireframe(
 }
 
 pub fn debug_show_trigger_color(
-    triggers: Query<&Children, (With<Trigger>, With<Marked>)>,
+    triggers: Query<(&TriggerTargets, &Children), With<Marked>>,
     mut wireframes: Query<&mut Wireframe2dColor>,
-    mut reader: EventReader<TriggerEvent>,
 ) {
-    for event in reader.read() {
-        if let Ok(children) = triggers.get(event.trigger) {
-            for child in children.iter() {
-                if let Ok(mut frame) = wireframes.get_mut(*child) {
-                    frame.color = Srgba::GREEN.into();
-                }
+    for (targets, children) in triggers.iter() {
+        let color = if targets.is_empty() {
+            Srgba::WHITE
+        } else {
+            Srgba::GREEN
+        };
+
+        for child in children.iter() {
+            if let Ok(mut frame) = wireframes.get_mut(*child) {
+                frame.color = color.into();
             }
         }
     }
@@ -145,3 +238,128 @@ pub fn debug_show_collision_color(
         }
     }
 }
+
+pub fn debug_draw_hash_cells(mut gizmos: Gizmos<PhysicsGizmos>, maps: Query<&SpatialHash>) {
+    for map in maps.iter() {
+        let size = map.cell_size();
+        for ((x, y), objects) in map.occupied_cells() {
+            let center = (Vec2::new(x as f32, y as f32) + 0.5) * size;
+            let alpha = (0.2 * objects as f32).min(1.);
+
+            gizmos.rect_2d(
+                Isometry2d::from_translation(center),
+                Vec2::splat(size),
+                Srgba::new(0.3, 0.6, 1., alpha),
+            );
+        }
+    }
+}
+
+pub fn debug_draw_vectors(
+    mut gizmos: Gizmos<PhysicsGizmos>,
+    bodies: Query<(&GlobalTransform, &Collider, &Velocity, &Acceleration), With<DynamicBody>>,
+) {
+    for (transform, collider, velocity, acceleration) in bodies.iter() {
+        let center = collider.global_absolute(transform).center();
+
+        gizmos.arrow_2d(center, center + velocity.0 * 0.1, Srgba::GREEN);
+        gizmos.arrow_2d(center, center + acceleration.total_force(), Srgba::RED);
+    }
+}
+
+pub fn debug_draw_contacts(
+    mut gizmos: Gizmos<PhysicsGizmos>,
+    bodies: Query<(&GlobalTransform, &Collider, &Collisions), With<DynamicBody>>,
+) {
+    for (transform, collider, collisions) in bodies.iter() {
+        let center = collider.global_absolute(transform).center();
+
+        for contact in collisions.contacts() {
+            gizmos.arrow_2d(
+                center,
+                center + contact.normal * (8. + contact.penetration),
+                Srgba::new(1., 0.5, 0., 1.),
+            );
+        }
+    }
+}
+
+pub fn debug_draw_body_states(
+    mut gizmos: Gizmos<PhysicsGizmos>,
+    bodies: Query<
+        (
+            &GlobalTransform,
+            &Collider,
+            Has<Grounded>,
+            Has<BrushingLeft>,
+            Has<BrushingRight>,
+            Has<Ceilinged>,
+        ),
+        With<DynamicBody>,
+    >,
+) {
+    for (transform, collider, grounded, left, right, ceilinged) in bodies.iter() {
+        let collider = collider.global_absolute(transform);
+        let (min, max) = (
+            Vec2::new(collider.min_x(), collider.min_y()),
+            Vec2::new(collider.max_x(), collider.max_y()),
+        );
+
+        if grounded {
+            gizmos.line_2d(min, Vec2::new(max.x, min.y), Srgba::GREEN);
+        }
+
+        if left {
+            gizmos.line_2d(min, Vec2::new(min.x, max.y), Srgba::BLUE);
+        }
+
+        if right {
+            gizmos.line_2d(Vec2::new(max.x, min.y), max, Srgba::BLUE);
+        }
+
+        if ceilinged {
+            gizmos.line_2d(Vec2::new(min.x, max.y), max, Srgba::RED);
+        }
+    }
+}
+
+pub fn debug_draw_trigger_overlaps(
+    mut gizmos: Gizmos<PhysicsGizmos>,
+    triggers: Query<(&GlobalTransform, &Trigger, &TriggerTargets)>,
+    targets: Query<(&GlobalTransform, &Collider)>,
+) {
+    for (transform, trigger, trigger_targets) in triggers.iter() {
+        let center = trigger.0.global_absolute(transform).center();
+
+        for (target_transform, collider) in targets.iter_many(trigger_targets.entities()) {
+            gizmos.line_2d(
+                center,
+                collider.global_absolute(target_transform).center(),
+                Srgba::GREEN,
+            );
+        }
+    }
+}
+
+pub fn debug_draw_constraints(
+    mut gizmos: Gizmos<PhysicsGizmos>,
+    constraints: Query<&DistanceConstraint>,
+) {
+    for constraint in constraints.iter() {
+        let Some((body, anchor)) = constraint.ends() else {
+            continue;
+        };
+
+        let slack =
+            constraint.kind == ConstraintKind::Rope && body.distance(anchor) < constraint.length;
+        gizmos.line_2d(
+            body,
+            anchor,
+            if slack {
+                Srgba::new(1., 1., 0., 1.)
+            } else {
+                Srgba::RED
+            },
+        );
+    }
+}
@@ -1,39 +1,137 @@
 use crate::prelude::Velocity;
 
-use super::prelude::Acceleration;
+use super::{
+    collision::{Collider, CollidesWith},
+    layers::{TriggerLayers, TriggersWith},
+    prelude::Acceleration,
+    sleep::Sleeping,
+    trigger::Trigger,
+};
 use bevy::prelude::*;
 
 /// Global definition of the gravity force.
 #[derive(Debug, Clone, Copy, Resource)]
 pub struct Gravity(pub Vec2);
 
-/// An entity who is not falling.
-#[derive(Debug, Default, Clone, Copy, Component)]
-pub struct Grounded;
+/// An entity who is not falling, standing on `entity`, a static, kinematic or dynamic body.
+#[derive(Debug, Clone, Copy, Component)]
+pub struct Grounded {
+    pub entity: Entity,
+    /// The normal of the surface stood on, [`Vec2::Y`] on flat ground.
+    pub normal: Vec2,
+}
 
-/// An entity that's brushing a left or right wall.
-#[derive(Debug, Default, Clone, Copy, Component)]
-pub struct BrushingLeft;
+/// An entity that's touching a ceiling above it, which is stored.
+#[derive(Debug, Clone, Copy, Component)]
+pub struct Ceilinged(pub Entity);
 
-/// An entity that's brushing a left or right wall.
-#[derive(Debug, Default, Clone, Copy, Component)]
-pub struct BrushingRight;
+/// An entity that's brushing a wall on its left, which is stored.
+#[derive(Debug, Clone, Copy, Component)]
+pub struct BrushingLeft(pub Entity);
+
+/// An entity that's brushing a wall on its right, which is stored.
+#[derive(Debug, Clone, Copy, Component)]
+pub struct BrushingRight(pub Entity);
 
 /// An entity who experiences [`Gravity`].
 #[derive(Debug, Default, Clone, Copy, Component)]
+#[require(GravityScale, TerminalVelocity, TriggersWith<GravityZone>)]
 pub struct Gravitational;
 
+/// Scales the [`Gravity`] experienced by a [`Gravitational`] entity.
+#[derive(Debug, Clone, Copy, Component)]
+pub struct GravityScale(pub f32);
+
+impl Default for GravityScale {
+    fn default() -> Self {
+        Self(1.)
+    }
+}
+
+/// The speed along the direction of gravity past which a [`Gravitational`] entity stops
+/// accelerating.
+#[derive(Debug, Clone, Copy, Component)]
+pub struct TerminalVelocity(pub f32);
+
+impl Default for TerminalVelocity {
+    fn default() -> Self {
+        Self(300.)
+    }
+}
+
+/// Changes the [`Gravity`] of [`Gravitational`] entities within the [`Trigger`] of this entity.
+///
+/// Overlapping zones are applied one after the other. Entities are tested against zones at
+/// their current position, rather than through the previous tick's trigger events, so zones
+/// apply from the tick an entity enters them until the tick it leaves.
+#[derive(Debug, Clone, Copy, Component)]
+#[require(Trigger)]
+pub struct GravityZone {
+    /// Replaces the direction of gravity, keeping its magnitude.
+    pub direction: Option<Dir2>,
+    /// Scales the magnitude of gravity.
+    pub scale: f32,
+}
+
+impl Default for GravityZone {
+    fn default() -> Self {
+        Self {
+            direction: None,
+            scale: 1.,
+        }
+    }
+}
+
+impl GravityZone {
+    pub fn apply(&self, gravity: Vec2) -> Vec2 {
+        self.direction
+            .map_or(gravity, |direction| direction * gravity.length())
+            * self.scale
+    }
+}
+
 pub fn apply_gravity(
     gravity: Res<Gravity>,
+    zones: Query<(&GravityZone, &Trigger, &GlobalTransform, &TriggerLayers)>,
     mut object_query: Query<
-        (&mut Acceleration, &Velocity),
-        (With<Gravitational>, Without<Grounded>),
+        (
+            &mut Acceleration,
+            &Velocity,
+            &GravityScale,
+            &TerminalVelocity,
+            (&GlobalTransform, &Collider, Option<&TriggerLayers>),
+            Has<Grounded>,
+        ),
+        (With<Gravitational>, Without<Sleeping>),
     >,
 ) {
-    let max_gravity = -300.;
-    for (mut acceleration, velocity) in object_query.iter_mut() {
-        if velocity.0.y > max_gravity {
-            acceleration.apply_force(gravity.0);
+    let zones = zones
+        .iter()
+        .map(|(zone, trigger, transform, layers)| {
+            (zone, trigger.0.global_absolute(transform), layers)
+        })
+        .collect::<Vec<_>>();
+
+    for (mut acceleration, velocity, scale, terminal, (transform, collider, layers), grounded) in
+        object_query.iter_mut()
+    {
+        let collider = collider.global_absolute(transform);
+        let force = zones
+            .iter()
+            .filter(|(_, zone_collider, zone_layers)| {
+                layers.is_some_and(|layers| layers.triggers_with(zone_layers))
+                    && collider.collides_with(zone_collider)
+            })
+            .fold(gravity.0, |gravity, (zone, ..)| zone.apply(gravity))
+            * scale.0;
+
+        // Grounded entities are only lifted off of the ground.
+        if grounded && force.y <= 0. {
+            continue;
+        }
+
+        if velocity.0.dot(force.normalize_or_zero()) < terminal.0 {
+            acceleration.apply_force(force);
         }
     }
 }
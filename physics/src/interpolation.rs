@@ -0,0 +1,71 @@
+use bevy::prelude::*;
+
+/// Smooths the rendered [`Transform`] translation of a body between physics ticks.
+///
+/// The simulation only ever sees the translation at the end of the last tick. Outside of the
+/// fixed main loop, the translation is interpolated between the last two ticks using the
+/// [`Time<Fixed>`] overstep.
+///
+/// Moving the [`Transform`] outside of the fixed main loop teleports the body.
+#[derive(Debug, Default, Clone, Copy, Component)]
+pub struct TransformInterpolation {
+    previous: Option<Vec3>,
+    current: Option<Vec3>,
+    rendered: Option<Vec3>,
+}
+
+impl TransformInterpolation {
+    /// Moves the body to `translation` without interpolating from its last position.
+    pub(crate) fn teleport(&mut self, translation: Vec3) {
+        self.previous = Some(translation);
+        self.current = Some(translation);
+        self.rendered = None;
+    }
+}
+
+pub(crate) fn restore_simulated_transforms(
+    mut query: Query<(&mut Transform, &mut TransformInterpolation)>,
+) {
+    for (mut transform, mut interpolation) in query.iter_mut() {
+        match (interpolation.rendered, interpolation.current) {
+            (Some(rendered), Some(current)) if rendered == transform.translation => {
+                transform.translation = current;
+            }
+            _ => {
+                interpolation.previous = Some(transform.translation);
+                interpolation.current = Some(transform.translation);
+            }
+        }
+    }
+}
+
+pub(crate) fn store_previous_translations(
+    mut query: Query<(&Transform, &mut TransformInterpolation)>,
+) {
+    for (transform, mut interpolation) in query.iter_mut() {
+        interpolation.previous = Some(transform.translation);
+    }
+}
+
+pub(crate) fn store_current_translations(
+    mut query: Query<(&Transform, &mut TransformInterpolation)>,
+) {
+    for (transform, mut interpolation) in query.iter_mut() {
+        interpolation.current = Some(transform.translation);
+    }
+}
+
+pub(crate) fn interpolate_transforms(
+    mut query: Query<(&mut Transform, &mut TransformInterpolation)>,
+    time: Res<Time<Fixed>>,
+) {
+    let alpha = time.overstep_fraction();
+
+    for (mut transform, mut interpolation) in query.iter_mut() {
+        if let (Some(previous), Some(current)) = (interpolation.previous, interpolation.current) {
+            let rendered = previous.lerp(current, alpha);
+            transform.translation = rendered;
+            interpolation.rendered = Some(rendered);
+        }
+    }
+}
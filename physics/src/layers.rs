@@ -1,12 +1,148 @@
 use super::prelude::{Collision, Triggers};
-use bevy::prelude::*;
+use bevy::{
+    ecs::{observer::Trigger as Observed, world::DeferredWorld},
+    prelude::*,
+    utils::hashbrown::HashMap,
+};
 use core::marker::PhantomData;
+use std::any::TypeId;
+
+/// A set of physics layers, one bit for each layer in [`PhysicsLayers`].
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct LayerMask(pub u32);
+
+impl LayerMask {
+    pub const NONE: Self = Self(0);
+    pub const ALL: Self = Self(u32::MAX);
+
+    pub fn intersects(&self, other: Self) -> bool {
+        self.0 & other.0 != 0
+    }
+
+    pub fn insert(&mut self, other: Self) {
+        self.0 |= other.0;
+    }
+
+    pub fn remove(&mut self, other: Self) {
+        self.0 &= !other.0;
+    }
+}
+
+impl core::ops::BitOr for LayerMask {
+    type Output = Self;
+
+    fn bitor(self, rhs: Self) -> Self::Output {
+        Self(self.0 | rhs.0)
+    }
+}
+
+impl core::ops::BitAnd for LayerMask {
+    type Output = Self;
+
+    fn bitand(self, rhs: Self) -> Self::Output {
+        Self(self.0 & rhs.0)
+    }
+}
+
+/// Assigns a [`LayerMask`] bit to every layer component registered with
+/// [`RegisterPhysicsLayer`].
+#[derive(Debug, Default, Resource)]
+pub struct PhysicsLayers {
+    layers: HashMap<TypeId, LayerMask>,
+    collision: LayerMask,
+    trigger: LayerMask,
+    grounded: LayerMask,
+    brushing: LayerMask,
+    ceiling: LayerMask,
+}
+
+impl PhysicsLayers {
+    /// Returns the mask of layer `T`, if it has been registered.
+    pub fn get<T: Component>(&self) -> Option<LayerMask> {
+        self.layers.get(&TypeId::of::<T>()).copied()
+    }
+
+    /// Layers that bodies can stand on.
+    pub fn grounded(&self) -> LayerMask {
+        self.grounded
+    }
+
+    /// Layers that bodies can brush against.
+    pub fn brushing(&self) -> LayerMask {
+        self.brushing
+    }
+
+    /// Layers that bodies can bump their heads on.
+    pub fn ceiling(&self) -> LayerMask {
+        self.ceiling
+    }
+
+    fn register<T: Component>(&mut self) -> LayerMask {
+        let next = self.layers.len() as u32;
+        *self.layers.entry(TypeId::of::<T>()).or_insert_with(|| {
+            assert!(next < u32::BITS, "at most 32 physics layers are supported");
+            LayerMask(1 << next)
+        })
+    }
+}
+
+/// The collision layers a body belongs to and the layers it resolves collisions against.
+///
+/// [`SpatialHash`](super::spatial::SpatialHash) entities share their memberships with every
+/// static body they contain. Both fields can be changed at runtime, for example clearing
+/// `filters` makes a body intangible.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Component)]
+#[require(super::collision::Collisions)]
+pub struct CollisionLayers {
+    pub memberships: LayerMask,
+    pub filters: LayerMask,
+}
+
+impl CollisionLayers {
+    pub fn new(memberships: LayerMask, filters: LayerMask) -> Self {
+        Self {
+            memberships,
+            filters,
+        }
+    }
+
+    /// Whether this body resolves collisions against `other`.
+    pub fn collides_with(&self, other: &Self) -> bool {
+        self.filters.intersects(other.memberships)
+    }
+}
+
+/// The trigger layers an entity belongs to and the layers of [`Trigger`](super::trigger::Trigger)s
+/// it can enter.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Component)]
+#[require(super::trigger::TriggerOverlaps)]
+#[component(on_remove = super::spatial::remove_from_broadphase)]
+pub struct TriggerLayers {
+    pub memberships: LayerMask,
+    pub filters: LayerMask,
+}
+
+impl TriggerLayers {
+    pub fn new(memberships: LayerMask, filters: LayerMask) -> Self {
+        Self {
+            memberships,
+            filters,
+        }
+    }
+
+    /// Whether this entity is triggered by a trigger in `other`.
+    pub fn triggers_with(&self, other: &Self) -> bool {
+        self.filters.intersects(other.memberships)
+    }
+}
 
 /// A marker component that can be placed on dynamic bodies
 /// to enable collisions between the dynamic body and the
 /// target static or dynamic bodies.
+///
+/// Adds `T` to the body's [`CollisionLayers`] filters.
 #[derive(Debug, Component)]
-#[require(Collision<T>)]
+#[require(Collision<T>, CollisionLayers)]
 pub struct CollidesWith<T: Component>(PhantomData<T>);
 
 impl<T: Component> Default for CollidesWith<T> {
@@ -18,8 +154,10 @@ impl<T: Component> Default for CollidesWith<T> {
 /// A marker component that can be placed on any body
 /// to enable triggering between the body and the
 /// target trigger bodies.
+///
+/// Adds `T` to the body's [`TriggerLayers`] filters.
 #[derive(Debug, Component)]
-#[require(Triggers<T>)]
+#[require(Triggers<T>, TriggerLayers)]
 pub struct TriggersWith<T: Component>(PhantomData<T>);
 
 impl<T: Component> Default for TriggersWith<T> {
@@ -37,48 +175,158 @@ pub struct Enemy;
 #[derive(Default, Debug, Component)]
 pub struct Player;
 
+/// Registers a layer component `T` in [`PhysicsLayers`].
+///
+/// Entities with `T` become members of the layer, while [`CollidesWith<T>`] and
+/// [`TriggersWith<T>`] add it to their filters. Registering a layer more than once does nothing.
+///
+/// Grounded, brushing and ceiling layers are registered as collision layers as well.
 pub trait RegisterPhysicsLayer {
     fn register_trigger_layer<T: Component>(&mut self) -> &mut Self;
     fn register_collision_layer<T: Component>(&mut self) -> &mut Self;
     fn register_grounded_layer<T: Component>(&mut self) -> &mut Self;
     fn register_brushing_layer<T: Component>(&mut self) -> &mut Self;
+    fn register_ceiling_layer<T: Component>(&mut self) -> &mut Self;
 }
 
 impl RegisterPhysicsLayer for App {
     fn register_trigger_layer<T: Component>(&mut self) -> &mut Self {
+        let mut layers = self
+            .world_mut()
+            .get_resource_or_insert_with(PhysicsLayers::default);
+        let mask = layers.register::<T>();
+        if layers.trigger.intersects(mask) {
+            return self;
+        }
+        layers.trigger.insert(mask);
+
+        super::register_required::<T, TriggerLayers>(self);
         self.add_systems(
             super::Physics,
-            super::trigger::handle_triggers::<T>.in_set(super::PhysicsSystems::Collision),
+            super::trigger::sync_triggers::<T>
+                .after(super::CollisionSystems::Triggers)
+                .in_set(super::PhysicsSystems::Collision),
         )
+        .add_observer(insert_membership::<T, TriggerLayers>)
+        .add_observer(remove_membership::<T, TriggerLayers>)
+        .add_observer(insert_filter::<T, TriggersWith<T>, TriggerLayers>)
+        .add_observer(remove_filter::<T, TriggersWith<T>, TriggerLayers>)
     }
 
     fn register_collision_layer<T: Component>(&mut self) -> &mut Self {
+        let mut layers = self
+            .world_mut()
+            .get_resource_or_insert_with(PhysicsLayers::default);
+        let mask = layers.register::<T>();
+        if layers.collision.intersects(mask) {
+            return self;
+        }
+        layers.collision.insert(mask);
+
+        super::register_required::<T, CollisionLayers>(self);
         self.add_systems(
             super::Physics,
-            (
-                super::collision::handle_collisions::<T>,
-                super::collision::handle_dynamic_body_collsions::<T>,
-            )
-                .chain()
-                .in_set(super::CollisionSystems::Resolution),
+            super::collision::sync_collisions::<T>.in_set(super::PhysicsSystems::Collision),
         )
+        .add_observer(insert_membership::<T, CollisionLayers>)
+        .add_observer(remove_membership::<T, CollisionLayers>)
+        .add_observer(insert_filter::<T, CollidesWith<T>, CollisionLayers>)
+        .add_observer(remove_filter::<T, CollidesWith<T>, CollisionLayers>)
     }
 
     fn register_grounded_layer<T: Component>(&mut self) -> &mut Self {
-        self.add_systems(
-            super::Physics,
-            super::collision::update_grounded::<T>
-                .chain()
-                .in_set(super::CollisionSystems::Grounding),
-        )
+        self.register_collision_layer::<T>();
+        let mut layers = self.world_mut().resource_mut::<PhysicsLayers>();
+        let mask = layers.register::<T>();
+        layers.grounded.insert(mask);
+        self
     }
 
     fn register_brushing_layer<T: Component>(&mut self) -> &mut Self {
-        self.add_systems(
-            super::Physics,
-            super::collision::update_brushing::<T>
-                .chain()
-                .in_set(super::CollisionSystems::Brushing),
-        )
+        self.register_collision_layer::<T>();
+        let mut layers = self.world_mut().resource_mut::<PhysicsLayers>();
+        let mask = layers.register::<T>();
+        layers.brushing.insert(mask);
+        self
+    }
+
+    fn register_ceiling_layer<T: Component>(&mut self) -> &mut Self {
+        self.register_collision_layer::<T>();
+        let mut layers = self.world_mut().resource_mut::<PhysicsLayers>();
+        let mask = layers.register::<T>();
+        layers.ceiling.insert(mask);
+        self
+    }
+}
+
+trait Layers: Component {
+    fn memberships_mut(&mut self) -> &mut LayerMask;
+    fn filters_mut(&mut self) -> &mut LayerMask;
+}
+
+impl Layers for CollisionLayers {
+    fn memberships_mut(&mut self) -> &mut LayerMask {
+        &mut self.memberships
     }
+
+    fn filters_mut(&mut self) -> &mut LayerMask {
+        &mut self.filters
+    }
+}
+
+impl Layers for TriggerLayers {
+    fn memberships_mut(&mut self) -> &mut LayerMask {
+        &mut self.memberships
+    }
+
+    fn filters_mut(&mut self) -> &mut LayerMask {
+        &mut self.filters
+    }
+}
+
+fn update_layers<T: Component, L: Layers>(
+    mut world: DeferredWorld,
+    entity: Entity,
+    update: impl FnOnce(&mut L, LayerMask),
+) {
+    let Some(mask) = world.resource::<PhysicsLayers>().get::<T>() else {
+        return;
+    };
+
+    if let Some(mut layers) = world.get_mut::<L>(entity) {
+        update(&mut layers, mask);
+    }
+}
+
+fn insert_membership<T: Component, L: Layers>(trigger: Observed<OnAdd, T>, world: DeferredWorld) {
+    update_layers::<T, L>(world, trigger.entity(), |l, mask| {
+        l.memberships_mut().insert(mask)
+    });
+}
+
+fn remove_membership<T: Component, L: Layers>(
+    trigger: Observed<OnRemove, T>,
+    world: DeferredWorld,
+) {
+    update_layers::<T, L>(world, trigger.entity(), |l, mask| {
+        l.memberships_mut().remove(mask)
+    });
+}
+
+fn insert_filter<T: Component, F: Component, L: Layers>(
+    trigger: Observed<OnAdd, F>,
+    world: DeferredWorld,
+) {
+    update_layers::<T, L>(world, trigger.entity(), |l, mask| {
+        l.filters_mut().insert(mask)
+    });
+}
+
+fn remove_filter<T: Component, F: Component, L: Layers>(
+    trigger: Observed<OnRemove, F>,
+    world: DeferredWorld,
+) {
+    update_layers::<T, L>(world, trigger.entity(), |l, mask| {
+        l.filters_mut().remove(mask)
+    });
 }
@@ -1,63 +1,69 @@
 use bevy::app::FixedMainScheduleOrder;
 use bevy::render::camera::PhysicalCameraParameters;
 use bevy::sprite::Wireframe2dPlugin;
-use bevy::{ecs::schedule::ScheduleLabel, prelude::*};
-use bevy_tween::prelude::Interpolator;
+use bevy::{
+    ecs::{component::RequiredComponentsError, schedule::ScheduleLabel},
+    prelude::*,
+};
+use bevy_pixel_gfx::camera::MainCamera;
 use bevy_tween::{component_tween_system, BevyTweenRegisterSystems};
 use layers::RegisterPhysicsLayer;
 
 pub mod collision;
+pub mod constraint;
 pub mod debug;
 pub mod gravity;
+pub mod interpolation;
 pub mod layers;
+pub mod material;
+pub mod query;
+pub mod sleep;
+pub mod snapshot;
 pub mod spatial;
+pub mod stats;
+pub mod time;
+pub mod timestep;
 pub mod trigger;
 pub mod velocity;
 
 #[allow(unused)]
 pub mod prelude {
     pub use super::collision::*;
+    pub use super::constraint::*;
     pub use super::gravity::*;
+    pub use super::interpolation::*;
     pub use super::layers;
+    pub use super::material::*;
+    pub use super::query::*;
+    pub use super::sleep::*;
+    pub use super::snapshot::{PhysicsHistory, PhysicsSnapshot};
+    pub use super::stats::{PhysicsStats, PhysicsStatsReport};
+    pub use super::timestep::*;
     pub use super::trigger::*;
     pub use super::velocity::*;
 }
 
-#[derive(Debug, Component)]
-pub struct TimeScale(pub f32);
-
-#[derive(Debug, Component)]
-pub struct TimeScaleRate {
-    start: f32,
-    end: f32,
-}
-
-impl TimeScaleRate {
-    pub fn new(start: f32, end: f32) -> Self {
-        Self { start, end }
-    }
-}
-
-impl Interpolator for TimeScaleRate {
-    type Item = TimeScale;
-
-    fn interpolate(&self, item: &mut Self::Item, value: f32) {
-        item.0 = self.start.lerp(self.end, value);
-    }
-}
-
-pub fn time_scale(start: f32, end: f32) -> TimeScaleRate {
-    TimeScaleRate::new(start, end)
-}
+pub use query::SpatialQuery;
+pub use time::{
+    local_time_scale, time_scale, GlobalTimeScale, LocalTimeScale, LocalTimeScaleRate, TimeGroup,
+    TimeScale, TimeScaleRate, TimeScales,
+};
 
 #[derive(Debug, Clone, Copy, Hash, PartialEq, Eq, ScheduleLabel)]
 pub struct Physics;
 
+/// Integrates positions and resolves collisions, run [`timestep::PhysicsTimestep::substeps`]
+/// times within the [`PhysicsSystems::Velocity`] set of every [`Physics`] tick.
+#[derive(Debug, Clone, Copy, Hash, PartialEq, Eq, ScheduleLabel)]
+pub struct PhysicsSubstep;
+
 #[derive(Debug, Clone, Copy, Hash, PartialEq, Eq, SystemSet)]
 pub enum CollisionSystems {
     Resolution,
     Grounding,
     Brushing,
+    Ceiling,
+    Triggers,
 }
 
 #[derive(Debug, Clone, Copy, Hash, PartialEq, Eq, SystemSet)]
@@ -69,57 +75,197 @@ pub enum PhysicsSystems {
 #[derive(Debug)]
 pub struct PhysicsPlugin;
 
+/// Makes `T` require `R`, warning when entities spawned with `T` beforehand are missing `R`.
+pub(crate) fn register_required<T: Component, R: Component + Default>(app: &mut App) {
+    if let Err(RequiredComponentsError::ArchetypeExists(_)) =
+        app.try_register_required_components::<T, R>()
+    {
+        warn!(
+            "`{}` was required by `{}` after it was spawned, existing entities are missing it",
+            std::any::type_name::<R>(),
+            std::any::type_name::<T>(),
+        );
+    }
+}
+
 impl Plugin for PhysicsPlugin {
     fn build(&self, app: &mut App) {
         app.init_schedule(Physics);
+        app.init_schedule(PhysicsSubstep);
         app.world_mut()
             .resource_mut::<FixedMainScheduleOrder>()
             .insert_after(FixedUpdate, Physics);
 
-        app.world_mut().spawn(TimeScale(1.));
+        time::spawn_time_scales(app.world_mut());
+
+        register_required::<MainCamera, sleep::ActivationRegion>(app);
 
         app.register_collision_layer::<layers::Player>()
             .register_collision_layer::<layers::Enemy>()
             .register_collision_layer::<layers::Wall>()
             .register_grounded_layer::<layers::Wall>()
-            .register_brushing_layer::<layers::Wall>();
+            .register_brushing_layer::<layers::Wall>()
+            .register_ceiling_layer::<layers::Wall>()
+            .register_trigger_layer::<gravity::GravityZone>();
 
         app.add_tween_systems(component_tween_system::<TimeScaleRate>())
+            .add_tween_systems(component_tween_system::<LocalTimeScaleRate>())
             .add_plugins(Wireframe2dPlugin)
             .add_event::<trigger::TriggerEvent>()
             .add_event::<trigger::TriggerEnter>()
+            .add_event::<trigger::TriggerStay>()
             .add_event::<trigger::TriggerExit>()
+            .add_event::<collision::CollisionStarted>()
+            .add_event::<collision::CollisionEnded>()
             .init_resource::<collision::TilesetSize>()
-            .insert_resource(debug::ShowCollision(false))
-            .add_systems(Update, collision::build_tile_set_colliders)
+            .init_resource::<collision::RemovedTiles>()
+            .init_resource::<timestep::PhysicsTimestep>()
+            .init_resource::<debug::PhysicsDebugConfig>()
+            .init_resource::<stats::PhysicsStats>()
+            .init_resource::<spatial::DynamicBroadphase>()
+            .init_resource::<sleep::SleepConfig>()
+            .insert_gizmo_config(debug::PhysicsGizmos, debug::gizmo_config())
             .add_systems(
-                Physics,
+                Update,
                 (
-                    collision::clear_resolution
-                        .in_set(PhysicsSystems::Collision)
-                        .before(CollisionSystems::Resolution),
-                    (gravity::apply_gravity, velocity::apply_velocity)
-                        .chain()
-                        .in_set(PhysicsSystems::Velocity),
+                    collision::build_tile_set_colliders,
+                    debug::update_show_collision,
+                ),
+            )
+            .add_systems(
+                Last,
+                stats::report_physics_stats.run_if(resource_exists::<stats::PhysicsStatsReport>),
+            )
+            .add_systems(
+                RunFixedMainLoop,
+                (
+                    (
+                        timestep::apply_physics_timestep
+                            .run_if(resource_changed::<timestep::PhysicsTimestep>),
+                        interpolation::restore_simulated_transforms,
+                    )
+                        .in_set(RunFixedMainLoopSystem::BeforeFixedMainLoop),
+                    interpolation::interpolate_transforms
+                        .in_set(RunFixedMainLoopSystem::AfterFixedMainLoop),
+                ),
+            )
+            .add_systems(FixedFirst, interpolation::store_previous_translations)
+            .add_systems(FixedLast, interpolation::store_current_translations)
+            .add_systems(
+                PhysicsSubstep,
+                (
+                    velocity::apply_velocity.in_set(PhysicsSystems::Velocity),
                     (
                         bevy::transform::systems::sync_simple_transforms,
                         bevy::transform::systems::propagate_transforms,
                         spatial::store_static_body_in_spatial_map,
+                        collision::update_kinematic_bodies,
                     )
                         .chain()
                         .before(PhysicsSystems::Collision)
                         .after(PhysicsSystems::Velocity),
                     (
-                        trigger::emit_trigger_states,
+                        constraint::solve_distance_constraints,
+                        stats::timed(collision::handle_continuous_collisions),
+                        // Every system moving bodies before the pairs are gathered has run.
+                        stats::timed(spatial::update_dynamic_broadphase),
+                        stats::timed(collision::handle_dynamic_body_collsions),
+                        stats::timed(collision::handle_collisions),
+                        collision::snap_to_slopes,
+                    )
+                        .chain()
+                        .in_set(CollisionSystems::Resolution),
+                ),
+            )
+            .add_systems(
+                Physics,
+                (
+                    snapshot::restore_physics_snapshot
+                        .run_if(snapshot::rewinding)
+                        .before(PhysicsSystems::Velocity),
+                    (
+                        stats::reset_physics_stats,
+                        collision::clear_resolution,
+                        gravity::apply_gravity,
+                        velocity::apply_acceleration,
+                        timestep::run_substeps,
+                    )
+                        .chain()
+                        .in_set(PhysicsSystems::Velocity),
+                    // Catches up with the bodies moved by the last substep's resolution.
+                    stats::timed(spatial::update_dynamic_broadphase)
+                        .in_set(PhysicsSystems::Collision)
+                        .before(CollisionSystems::Grounding)
+                        .before(CollisionSystems::Brushing)
+                        .before(CollisionSystems::Ceiling)
+                        .before(CollisionSystems::Triggers),
+                    stats::timed(collision::update_grounded).in_set(CollisionSystems::Grounding),
+                    stats::timed(collision::update_brushing).in_set(CollisionSystems::Brushing),
+                    stats::timed(collision::update_ceilinged).in_set(CollisionSystems::Ceiling),
+                    (
+                        collision::emit_collision_states.after(CollisionSystems::Resolution),
+                        (
+                            stats::timed(trigger::handle_triggers),
+                            stats::timed(trigger::emit_trigger_states),
+                        )
+                            .chain()
+                            .in_set(CollisionSystems::Triggers),
+                        collision::clear_drop_through.in_set(CollisionSystems::Grounding),
+                        sleep::update_sleeping
+                            .after(CollisionSystems::Triggers)
+                            .after(CollisionSystems::Grounding)
+                            .after(CollisionSystems::Brushing)
+                            .after(CollisionSystems::Ceiling),
+                        stats::update_physics_stats
+                            .after(sleep::update_sleeping)
+                            .after(CollisionSystems::Triggers)
+                            .after(CollisionSystems::Grounding)
+                            .after(CollisionSystems::Brushing)
+                            .after(CollisionSystems::Ceiling),
+                        snapshot::capture_physics_snapshot
+                            .run_if(resource_exists::<snapshot::PhysicsHistory>)
+                            .after(stats::update_physics_stats),
+                    )
+                        .in_set(PhysicsSystems::Collision),
+                ),
+            )
+            .add_systems(
+                Physics,
+                (
+                    debug::debug_draw_vectors
+                        .run_if(debug::debug_layer(|c| c.vectors))
+                        .after(gravity::apply_gravity)
+                        .before(velocity::apply_acceleration),
+                    (
                         debug::debug_display_collider_wireframe,
-                        debug::update_show_collision,
                         (
                             debug::debug_show_collision_color,
                             debug::debug_show_trigger_color,
                         )
-                            .chain(),
+                            .chain()
+                            .run_if(debug::debug_layer(|c| c.colliders))
+                            .after(CollisionSystems::Triggers),
+                        debug::debug_draw_hash_cells.run_if(debug::debug_layer(|c| c.hash_cells)),
+                        debug::debug_draw_contacts.run_if(debug::debug_layer(|c| c.contacts)),
+                        debug::debug_draw_body_states
+                            .run_if(debug::debug_layer(|c| c.body_states))
+                            .after(CollisionSystems::Grounding)
+                            .after(CollisionSystems::Brushing)
+                            .after(CollisionSystems::Ceiling),
+                        debug::debug_draw_constraints.run_if(debug::debug_layer(|c| c.constraints)),
+                        debug::debug_draw_trigger_overlaps
+                            .run_if(debug::debug_layer(|c| c.trigger_overlaps))
+                            .after(CollisionSystems::Triggers),
                     )
-                        .in_set(PhysicsSystems::Collision),
+                        .in_set(PhysicsSystems::Collision)
+                        .after(CollisionSystems::Resolution),
+                ),
+            )
+            .configure_sets(
+                PhysicsSubstep,
+                (
+                    PhysicsSystems::Velocity.before(PhysicsSystems::Collision),
+                    CollisionSystems::Resolution.in_set(PhysicsSystems::Collision),
                 ),
             )
             .configure_sets(
@@ -128,10 +274,13 @@ impl Plugin for PhysicsPlugin {
                     PhysicsSystems::Velocity.before(PhysicsSystems::Collision),
                     CollisionSystems::Resolution
                         .before(CollisionSystems::Grounding)
-                        .before(CollisionSystems::Brushing),
+                        .before(CollisionSystems::Brushing)
+                        .before(CollisionSystems::Ceiling),
                     CollisionSystems::Resolution.in_set(PhysicsSystems::Collision),
                     CollisionSystems::Grounding.in_set(PhysicsSystems::Collision),
                     CollisionSystems::Brushing.in_set(PhysicsSystems::Collision),
+                    CollisionSystems::Ceiling.in_set(PhysicsSystems::Collision),
+                    CollisionSystems::Triggers.in_set(PhysicsSystems::Collision),
                 ),
             );
     }
@@ -0,0 +1,94 @@
+use bevy::{
+    ecs::intern::{Interned, Interner},
+    prelude::*,
+};
+use std::fmt;
+
+static SURFACE_TAGS: Interner<str> = Interner::new();
+
+/// A user defined name for the kind of surface a [`PhysicsMaterial`] describes, such as
+/// `"ice"` or `"metal"`, used by gameplay to pick footstep sounds or effects.
+///
+/// Tags are interned, so they can be read from level data and still be copied and compared
+/// cheaply.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct SurfaceTag(Interned<str>);
+
+impl SurfaceTag {
+    pub fn new(name: &str) -> Self {
+        Self(SURFACE_TAGS.intern(name))
+    }
+
+    pub fn name(&self) -> &'static str {
+        self.0 .0
+    }
+}
+
+impl Default for SurfaceTag {
+    fn default() -> Self {
+        Self::new("")
+    }
+}
+
+impl From<&str> for SurfaceTag {
+    fn from(name: &str) -> Self {
+        Self::new(name)
+    }
+}
+
+impl fmt::Display for SurfaceTag {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+/// Describes how bodies respond when touching a collider.
+///
+/// Can be placed on static, kinematic and dynamic bodies, as well as on [`TilesetCollider`]
+/// tiles, in which case it is copied to the generated colliders. Colliders without a material
+/// use [`PhysicsMaterial::default`].
+///
+/// [`TilesetCollider`]: super::collision::TilesetCollider
+#[derive(Debug, Clone, Copy, PartialEq, Component)]
+pub struct PhysicsMaterial {
+    /// The fraction of velocity kept when bouncing off of the surface, `0.` does not bounce.
+    pub restitution: f32,
+    /// How strongly the surface holds bodies sliding along it, `1.` being a regular wall.
+    ///
+    /// Values below one are slippery, values above one are sticky. Collision resolution does not
+    /// apply friction, it is read by gameplay systems such as the player's wall slide.
+    pub friction: f32,
+    pub surface: SurfaceTag,
+}
+
+impl Default for PhysicsMaterial {
+    fn default() -> Self {
+        Self {
+            restitution: 0.,
+            friction: 1.,
+            surface: SurfaceTag::default(),
+        }
+    }
+}
+
+impl PhysicsMaterial {
+    pub fn new(restitution: f32, friction: f32, surface: SurfaceTag) -> Self {
+        Self {
+            restitution,
+            friction,
+            surface,
+        }
+    }
+
+    /// The material of a contact between a body made of `self` and a surface made of `other`.
+    ///
+    /// The bounciest restitution wins and frictions are multiplied, the surface tag is
+    /// taken from `other`.
+    pub fn combine(&self, other: &Self) -> Self {
+        Self {
+            restitution: self.restitution.max(other.restitution),
+            friction: self.friction * other.friction,
+            surface: other.surface,
+        }
+    }
+}
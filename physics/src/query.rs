@@ -0,0 +1,227 @@
+use super::{
+    collision::{AbsoluteCollider, Collider, CollidesWith, PolygonCollider, RectCollider},
+    layers::{CollisionLayers, LayerMask},
+    spatial::{DynamicBroadphase, RayHit, SpatialHash},
+};
+use bevy::{ecs::system::SystemParam, prelude::*};
+use smallvec::SmallVec;
+
+/// Restricts the colliders considered by a [`SpatialQuery`].
+///
+/// With layers, only colliders whose [`CollisionLayers`] memberships intersect them are
+/// considered, static bodies sharing the memberships of their [`SpatialHash`]. With no layers,
+/// every collider is considered.
+#[derive(Debug, Default, Clone)]
+pub struct SpatialQueryFilter {
+    layers: Option<LayerMask>,
+    excluded: SmallVec<[Entity; 2]>,
+}
+
+impl SpatialQueryFilter {
+    /// Include colliders in `layers`, see [`PhysicsLayers`](super::layers::PhysicsLayers).
+    pub fn with_layers(mut self, layers: LayerMask) -> Self {
+        self.layers = Some(self.layers.unwrap_or_default() | layers);
+        self
+    }
+
+    /// Ignore the collider belonging to `entity`.
+    pub fn without_entity(mut self, entity: Entity) -> Self {
+        self.excluded.push(entity);
+        self
+    }
+
+    fn accepts(&self, entity: Entity, layers: Option<&CollisionLayers>) -> bool {
+        !self.excluded.contains(&entity) && self.in_layers(layers)
+    }
+
+    fn in_layers(&self, layers: Option<&CollisionLayers>) -> bool {
+        self.layers
+            .is_none_or(|filter| layers.is_some_and(|layers| layers.memberships.intersects(filter)))
+    }
+}
+
+/// The first collider hit by [`SpatialQuery::shape_cast`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ShapeHit {
+    pub entity: Entity,
+    pub normal: Vec2,
+    /// Distance travelled by the shape before making contact.
+    pub distance: f32,
+}
+
+/// Queries the world's colliders, including every [`SpatialHash`] and
+/// [`DynamicBody`](super::collision::DynamicBody).
+///
+/// Dynamic bodies are found through the [`DynamicBroadphase`], at their positions as of its last
+/// update. Triggers are not considered.
+#[derive(SystemParam)]
+pub struct SpatialQuery<'w, 's> {
+    maps: Query<'w, 's, (&'static SpatialHash, Option<&'static CollisionLayers>)>,
+    broadphase: Res<'w, DynamicBroadphase>,
+}
+
+impl SpatialQuery<'_, '_> {
+    /// Returns all colliders containing `point`.
+    pub fn point(&self, point: Vec2, filter: &SpatialQueryFilter) -> Vec<Entity> {
+        let rect = RectCollider {
+            tl: point,
+            size: Vec2::ZERO,
+        };
+
+        self.colliders(&rect, filter)
+            .filter(|(_, c)| c.contains(&point))
+            .map(|(e, _)| e)
+            .collect()
+    }
+
+    /// Returns all colliders overlapping `shape`.
+    pub fn overlaps(&self, shape: &AbsoluteCollider, filter: &SpatialQueryFilter) -> Vec<Entity> {
+        self.colliders(&shape.get_aabb(), filter)
+            .filter(|(_, c)| shape.collides_with(c))
+            .map(|(e, _)| e)
+            .collect()
+    }
+
+    /// Returns all colliders overlapping the rect with top left corner `tl`.
+    pub fn aabb_overlaps(&self, tl: Vec2, size: Vec2, filter: &SpatialQueryFilter) -> Vec<Entity> {
+        self.overlaps(&Collider::from_rect(tl, size).absolute(&default()), filter)
+    }
+
+    /// Returns all colliders overlapping the circle at `position`.
+    pub fn circle_overlaps(
+        &self,
+        position: Vec2,
+        radius: f32,
+        filter: &SpatialQueryFilter,
+    ) -> Vec<Entity> {
+        self.overlaps(
+            &Collider::from_circle(position, radius).absolute(&default()),
+            filter,
+        )
+    }
+
+    /// Returns the closest collider hit by `ray` within `max_distance`.
+    ///
+    /// Returns `None` if `max_distance` is not finite.
+    pub fn ray_cast(
+        &self,
+        ray: Ray2d,
+        max_distance: f32,
+        filter: &SpatialQueryFilter,
+    ) -> Option<RayHit> {
+        if !max_distance.is_finite() {
+            return None;
+        }
+
+        let maps = self
+            .maps
+            .iter()
+            .filter(|(_, layers)| filter.in_layers(*layers))
+            .filter_map(|(map, _)| {
+                map.ray_cast(ray, max_distance, |d| !filter.excluded.contains(&d.entity))
+            });
+
+        let bodies = self.broadphase.ray_cast(ray, max_distance, |d| {
+            d.data.collision.is_some() && filter.accepts(d.entity, d.data.collision.as_ref())
+        });
+
+        maps.chain(bodies)
+            .min_by(|a, b| a.distance.total_cmp(&b.distance))
+    }
+
+    /// Moves `shape` along `direction` and returns the first collider it hits within
+    /// `max_distance`.
+    ///
+    /// Shapes are cast as their axis-aligned bounding boxes, against the exact shape of polygon
+    /// colliders and the bounding boxes of every other collider. Colliders already overlapping
+    /// `shape` are ignored.
+    pub fn shape_cast(
+        &self,
+        shape: &AbsoluteCollider,
+        direction: Dir2,
+        max_distance: f32,
+        filter: &SpatialQueryFilter,
+    ) -> Option<ShapeHit> {
+        let aabb = shape.get_aabb();
+        let motion = *direction * max_distance;
+
+        self.colliders(&aabb.swept(motion), filter)
+            .filter_map(|(entity, collider)| {
+                let (t, normal) = match collider {
+                    AbsoluteCollider::Polygon(polygon) => {
+                        PolygonCollider::from(aabb).sweep(motion, &polygon)?
+                    }
+                    other => aabb.sweep(motion, &other.get_aabb())?,
+                };
+                Some(ShapeHit {
+                    entity,
+                    normal,
+                    distance: t * max_distance,
+                })
+            })
+            .min_by(|a, b| a.distance.total_cmp(&b.distance))
+    }
+
+    /// Every collider within `rect` accepted by `filter`, each returned once.
+    fn colliders<'a>(
+        &'a self,
+        rect: &RectCollider,
+        filter: &'a SpatialQueryFilter,
+    ) -> impl Iterator<Item = (Entity, AbsoluteCollider)> + 'a {
+        let rect = *rect;
+        let statics = self
+            .maps
+            .iter()
+            .filter(|(_, layers)| filter.in_layers(*layers))
+            .flat_map(move |(map, _)| map.objects_in_rect(&rect))
+            .filter(|d| !filter.excluded.contains(&d.entity))
+            .map(|d| (d.entity, d.collider));
+
+        let bodies = self
+            .broadphase
+            .objects_in_rect(&rect)
+            .filter(|d| {
+                d.data.collision.is_some() && filter.accepts(d.entity, d.data.collision.as_ref())
+            })
+            .map(|d| (d.entity, d.collider));
+
+        statics.chain(bodies)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::{collision::Ramp, spatial::SpatialData};
+    use super::*;
+    use bevy::ecs::system::RunSystemOnce;
+
+    #[test]
+    fn shape_cast_hits_polygon_surface() {
+        let mut world = World::new();
+        world.init_resource::<DynamicBroadphase>();
+        let ramp = Entity::from_raw(0);
+        world.spawn(SpatialHash::new_with(
+            32.,
+            [SpatialData {
+                entity: ramp,
+                collider: Collider::from_ramp(Vec2::new(0., 32.), Vec2::splat(32.), Ramp::Right)
+                    .absolute(&Transform::default()),
+                data: (),
+            }],
+        ));
+
+        let hit = world
+            .run_system_once(|query: SpatialQuery| {
+                let shape = Collider::from_rect(Vec2::new(4., 50.), Vec2::splat(4.))
+                    .absolute(&Transform::default());
+                query.shape_cast(&shape, Dir2::NEG_Y, 64., &SpatialQueryFilter::default())
+            })
+            .unwrap()
+            .unwrap();
+
+        // The bottom right corner lands on the slope, not on top of the ramp's bounding box.
+        assert_eq!(hit.entity, ramp);
+        assert!((hit.distance - 38.).abs() < 0.1, "{}", hit.distance);
+        assert!(hit.normal.y > 0. && hit.normal.x < 0.);
+    }
+}
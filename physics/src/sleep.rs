@@ -0,0 +1,199 @@
+use super::{
+    collision::{Collider, CollidesWith, DynamicBody},
+    gravity::Grounded,
+    layers::CollisionLayers,
+    spatial::{DynamicBroadphase, SpatialHash},
+    trigger::TriggerEnter,
+    velocity::{Acceleration, Velocity},
+};
+use bevy::prelude::*;
+
+/// Why a [`DynamicBody`] is skipped by the [`Physics`](super::Physics) systems.
+///
+/// Sleeping bodies keep their state, including their contacts and grounded and brushing
+/// markers, and are still found by triggers and other bodies. Removing this component wakes the
+/// body, as does changing its [`Velocity`] or [`Acceleration`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Component)]
+pub enum Sleeping {
+    /// The body has been at rest on the ground for [`SleepConfig::rest_ticks`].
+    ///
+    /// Woken when touched by an awake body, when it enters or is entered by a trigger, when the
+    /// body it stands on moves or is despawned, or when a [`SpatialHash`] changes.
+    Resting,
+    /// The body is outside of every [`ActivationRegion`].
+    ///
+    /// Woken only once it is back within a region.
+    OutOfRange,
+}
+
+/// Counts the ticks a [`DynamicBody`] has been at rest on the ground for.
+#[derive(Debug, Default, Clone, Copy, Component)]
+pub struct SleepTimer {
+    ticks: u32,
+    /// The [`Grounded`] entity and its position when the body fell asleep.
+    support: Option<(Entity, Vec2)>,
+}
+
+impl SleepTimer {
+    pub fn ticks(&self) -> u32 {
+        self.ticks
+    }
+}
+
+/// How far the support of a resting body can move before the body is woken.
+const SUPPORT_TOLERANCE: f32 = 0.01;
+
+/// Keeps a [`DynamicBody`] from ever sleeping.
+#[derive(Debug, Default, Clone, Copy, Component)]
+pub struct NeverSleep;
+
+/// Simulates the [`DynamicBody`] entities within `margin` of this entity's [`Collider`].
+///
+/// Required by the main camera. With no regions, bodies only sleep when resting.
+#[derive(Debug, Clone, Copy, Component)]
+pub struct ActivationRegion {
+    pub margin: Vec2,
+}
+
+impl Default for ActivationRegion {
+    fn default() -> Self {
+        Self {
+            margin: Vec2::splat(128.),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Resource)]
+pub struct SleepConfig {
+    /// The number of ticks a body must be at rest for before it falls asleep.
+    pub rest_ticks: u32,
+    /// The speed below which a body is at rest.
+    pub rest_speed: f32,
+}
+
+impl Default for SleepConfig {
+    fn default() -> Self {
+        Self {
+            rest_ticks: 30,
+            rest_speed: 1.,
+        }
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+pub(crate) fn update_sleeping(
+    mut commands: Commands,
+    config: Res<SleepConfig>,
+    broadphase: Res<DynamicBroadphase>,
+    regions: Query<(&GlobalTransform, &Collider, &ActivationRegion)>,
+    mut bodies: Query<
+        (
+            Entity,
+            &GlobalTransform,
+            &Collider,
+            Option<&CollisionLayers>,
+            Ref<Velocity>,
+            Ref<Acceleration>,
+            &mut SleepTimer,
+            Option<&Sleeping>,
+            Option<&Grounded>,
+        ),
+        (With<DynamicBody>, Without<NeverSleep>),
+    >,
+    sleeping: Query<(
+        &Sleeping,
+        &GlobalTransform,
+        &Collider,
+        Option<&CollisionLayers>,
+    )>,
+    supports: Query<&GlobalTransform>,
+    maps: Query<Ref<SpatialHash>>,
+    mut entered: EventReader<TriggerEnter>,
+) {
+    let regions = regions
+        .iter()
+        .map(|(transform, collider, region)| {
+            let mut aabb = collider.global_absolute(transform).get_aabb();
+            aabb.tl += Vec2::new(-region.margin.x, region.margin.y);
+            aabb.size += region.margin * 2.;
+            aabb
+        })
+        .collect::<Vec<_>>();
+
+    let position = |entity| supports.get(entity).ok().map(|t| t.translation().xy());
+    let map_changed = maps.iter().any(|map| map.is_changed());
+
+    let mut woken = entered
+        .read()
+        .flat_map(|event| [event.trigger, event.target])
+        .collect::<Vec<_>>();
+
+    for (entity, transform, collider, layers, velocity, acceleration, mut timer, state, grounded) in
+        bodies.iter_mut()
+    {
+        let layers = layers.copied().unwrap_or_default();
+        let collider = collider.global_absolute(transform);
+        let aabb = collider.get_aabb();
+        if !regions.is_empty() && !regions.iter().any(|region| region.collides_with(&aabb)) {
+            if state != Some(&Sleeping::OutOfRange) {
+                commands.entity(entity).insert(Sleeping::OutOfRange);
+            }
+            *timer = SleepTimer::default();
+            continue;
+        }
+
+        match state {
+            Some(Sleeping::OutOfRange) => {
+                commands.entity(entity).remove::<Sleeping>();
+            }
+            Some(Sleeping::Resting) => {
+                let supported = timer.support.is_some_and(|(support, at)| {
+                    position(support).is_some_and(|p| p.distance(at) <= SUPPORT_TOLERANCE)
+                });
+                if velocity.is_changed() || acceleration.is_changed() || !supported || map_changed {
+                    woken.push(entity);
+                }
+            }
+            None => {
+                // Awake bodies wake the resting bodies they touch.
+                woken.extend(
+                    broadphase
+                        .objects_in_rect(&aabb)
+                        .filter(|d| d.entity != entity)
+                        .filter(|d| {
+                            sleeping.get(d.entity).is_ok_and(|(_, t, c, other)| {
+                                let other = other.copied().unwrap_or_default();
+                                (layers.collides_with(&other) || other.collides_with(&layers))
+                                    && collider.collides_with(&c.global_absolute(t))
+                            })
+                        })
+                        .map(|d| d.entity),
+                );
+
+                // Only bodies standing on something can rest, they are woken when it goes away.
+                let support = grounded.and_then(|g| position(g.entity).map(|p| (g.entity, p)));
+                if velocity.0.length() > config.rest_speed || support.is_none() {
+                    *timer = SleepTimer::default();
+                    continue;
+                }
+
+                timer.ticks += 1;
+                if timer.ticks >= config.rest_ticks {
+                    timer.support = support;
+                    commands.entity(entity).insert(Sleeping::Resting);
+                }
+            }
+        }
+    }
+
+    woken.sort_unstable();
+    woken.dedup();
+    for entity in woken {
+        if let Ok((Sleeping::Resting, ..)) = sleeping.get(entity) {
+            commands.entity(entity).remove::<Sleeping>();
+            if let Ok((.., mut timer, _, _)) = bodies.get_mut(entity) {
+                *timer = SleepTimer::default();
+            }
+        }
+    }
+}
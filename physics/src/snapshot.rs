@@ -0,0 +1,434 @@
+use super::{
+    collision::{Collider, DynamicBody, KinematicBody, StaticBody},
+    gravity::{BrushingLeft, BrushingRight, Ceilinged, Grounded},
+    interpolation::TransformInterpolation,
+    layers::{CollisionLayers, LayerMask, TriggerLayers},
+    sleep::Sleeping,
+    spatial::{BroadphaseData, DynamicBroadphase, SpatialData, SpatialHash},
+    trigger::{Trigger, TriggerOverlaps, TriggerTargets},
+    velocity::{Acceleration, Velocity},
+};
+use bevy::prelude::*;
+use smallvec::SmallVec;
+use std::{collections::VecDeque, sync::Arc};
+
+/// The physics state of a single entity within a [`PhysicsSnapshot`].
+#[derive(Debug, Clone)]
+struct BodySnapshot {
+    entity: Entity,
+    transform: Transform,
+    velocity: Option<Velocity>,
+    acceleration: Option<Acceleration>,
+    kinematic: Option<KinematicBody>,
+    grounded: Option<Grounded>,
+    brushing_left: Option<BrushingLeft>,
+    brushing_right: Option<BrushingRight>,
+    ceilinged: Option<Ceilinged>,
+    sleeping: Option<Sleeping>,
+    trigger_targets: Option<SmallVec<[Entity; 4]>>,
+    trigger_overlaps: Option<SmallVec<[(Entity, LayerMask); 4]>>,
+}
+
+/// The physics state of the world at the end of a [`Physics`](super::Physics) tick.
+#[derive(Debug, Clone)]
+pub struct PhysicsSnapshot {
+    tick: u64,
+    bodies: Vec<BodySnapshot>,
+    /// Shared with the previous snapshot while a map is unchanged.
+    maps: Vec<(Entity, Arc<SpatialHash>)>,
+}
+
+impl PhysicsSnapshot {
+    /// The number of ticks recorded before this snapshot was captured.
+    pub fn tick(&self) -> u64 {
+        self.tick
+    }
+
+    pub fn entities(&self) -> impl Iterator<Item = Entity> + '_ {
+        self.bodies.iter().map(|b| b.entity)
+    }
+}
+
+/// Records a [`PhysicsSnapshot`] at the end of every [`Physics`](super::Physics) tick, keeping
+/// the last `capacity` ticks.
+///
+/// Recording is disabled unless this resource is inserted. Snapshots hold the transforms,
+/// velocities, accelerations, grounded, brushing and sleeping states and trigger overlaps of
+/// every body and trigger, along with every [`SpatialHash`].
+///
+/// Restoring a snapshot does not respawn entities despawned since it was captured, nor
+/// despawn entities spawned after it. The restored maps drop the bodies despawned since and keep
+/// the ones spawned after, and the [`DynamicBroadphase`] is rebuilt from the restored bodies.
+#[derive(Debug, Resource)]
+pub struct PhysicsHistory {
+    capacity: usize,
+    snapshots: VecDeque<PhysicsSnapshot>,
+    tick: u64,
+    rewinding: bool,
+}
+
+impl Default for PhysicsHistory {
+    fn default() -> Self {
+        Self::new(180)
+    }
+}
+
+impl PhysicsHistory {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            capacity: capacity.max(1),
+            snapshots: VecDeque::with_capacity(capacity),
+            tick: 0,
+            rewinding: false,
+        }
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    pub fn len(&self) -> usize {
+        self.snapshots.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.snapshots.is_empty()
+    }
+
+    /// Returns the snapshot captured `ticks` ticks before the latest one.
+    pub fn get(&self, ticks: usize) -> Option<&PhysicsSnapshot> {
+        self.snapshots.iter().rev().nth(ticks)
+    }
+
+    pub fn latest(&self) -> Option<&PhysicsSnapshot> {
+        self.snapshots.back()
+    }
+
+    /// Restores the snapshot captured `ticks` ticks before the latest one at the start of the
+    /// next tick, discarding every newer snapshot.
+    ///
+    /// Returns false, without rewinding, if the history does not reach back that far.
+    pub fn rewind(&mut self, ticks: usize) -> bool {
+        if ticks >= self.snapshots.len() {
+            return false;
+        }
+
+        self.snapshots.truncate(self.snapshots.len() - ticks);
+        self.rewinding = true;
+        true
+    }
+
+    pub fn clear(&mut self) {
+        self.snapshots.clear();
+        self.rewinding = false;
+    }
+
+    fn push(&mut self, snapshot: PhysicsSnapshot) {
+        if self.snapshots.len() == self.capacity {
+            self.snapshots.pop_front();
+        }
+
+        self.snapshots.push_back(snapshot);
+        self.tick += 1;
+    }
+}
+
+pub(crate) fn rewinding(history: Option<Res<PhysicsHistory>>) -> bool {
+    history.is_some_and(|history| history.rewinding)
+}
+
+type CapturedBodies<'w, 's> = Query<
+    'w,
+    's,
+    (
+        Entity,
+        &'static Transform,
+        (
+            Option<&'static Velocity>,
+            Option<&'static Acceleration>,
+            Option<&'static KinematicBody>,
+        ),
+        (
+            Option<&'static Grounded>,
+            Option<&'static BrushingLeft>,
+            Option<&'static BrushingRight>,
+            Option<&'static Ceilinged>,
+            Option<&'static Sleeping>,
+        ),
+        (
+            Option<&'static TriggerTargets>,
+            Option<&'static TriggerOverlaps>,
+        ),
+    ),
+    Or<(
+        With<DynamicBody>,
+        With<KinematicBody>,
+        With<Trigger>,
+        With<TriggerOverlaps>,
+    )>,
+>;
+
+type RestoredBodies<'w, 's> = Query<
+    'w,
+    's,
+    (
+        &'static mut Transform,
+        Option<&'static mut TransformInterpolation>,
+        (
+            Option<&'static mut Velocity>,
+            Option<&'static mut Acceleration>,
+            Option<&'static mut KinematicBody>,
+        ),
+        (
+            Option<&'static mut TriggerTargets>,
+            Option<&'static mut TriggerOverlaps>,
+        ),
+    ),
+>;
+
+type MapBodies<'w, 's> = Query<
+    'w,
+    's,
+    (
+        Entity,
+        &'static GlobalTransform,
+        &'static Collider,
+        &'static Parent,
+    ),
+    Or<(With<StaticBody>, With<KinematicBody>)>,
+>;
+
+type BroadphaseBodies<'w, 's> = Query<
+    'w,
+    's,
+    (
+        Entity,
+        &'static GlobalTransform,
+        &'static Collider,
+        Option<&'static CollisionLayers>,
+        Option<&'static TriggerLayers>,
+        Has<DynamicBody>,
+    ),
+>;
+
+pub(crate) fn capture_physics_snapshot(
+    mut history: ResMut<PhysicsHistory>,
+    bodies: CapturedBodies,
+    maps: Query<(Entity, Ref<SpatialHash>)>,
+) {
+    let bodies = bodies
+        .iter()
+        .map(
+            |(
+                entity,
+                transform,
+                (velocity, acceleration, kinematic),
+                (grounded, brushing_left, brushing_right, ceilinged, sleeping),
+                (trigger_targets, trigger_overlaps),
+            )| BodySnapshot {
+                entity,
+                transform: *transform,
+                velocity: velocity.copied(),
+                acceleration: acceleration.cloned(),
+                kinematic: kinematic.copied(),
+                grounded: grounded.copied(),
+                brushing_left: brushing_left.copied(),
+                brushing_right: brushing_right.copied(),
+                ceilinged: ceilinged.copied(),
+                sleeping: sleeping.copied(),
+                trigger_targets: trigger_targets.map(|t| t.0.clone()),
+                trigger_overlaps: trigger_overlaps.map(|o| o.0.clone()),
+            },
+        )
+        .collect();
+
+    let previous = history.latest().map(|s| &s.maps);
+    let maps = maps
+        .iter()
+        .map(|(entity, map)| {
+            let unchanged = previous
+                .and_then(|maps| maps.iter().find(|(e, _)| *e == entity))
+                .filter(|_| !map.is_changed())
+                .map(|(_, map)| map.clone());
+
+            (entity, unchanged.unwrap_or_else(|| Arc::new(map.clone())))
+        })
+        .collect();
+
+    let snapshot = PhysicsSnapshot {
+        tick: history.tick,
+        bodies,
+        maps,
+    };
+    history.push(snapshot);
+}
+
+pub(crate) fn restore_physics_snapshot(
+    mut commands: Commands,
+    mut history: ResMut<PhysicsHistory>,
+    mut bodies: RestoredBodies,
+    mut maps: Query<&mut SpatialHash>,
+    map_bodies: MapBodies,
+    mut broadphase: ResMut<DynamicBroadphase>,
+    broadphase_bodies: BroadphaseBodies,
+) {
+    history.rewinding = false;
+    let Some(snapshot) = history.snapshots.back().cloned() else {
+        return;
+    };
+    history.tick = snapshot.tick + 1;
+
+    for body in snapshot.bodies {
+        let Ok((
+            mut transform,
+            interpolation,
+            (velocity, acceleration, kinematic),
+            (trigger_targets, trigger_overlaps),
+        )) = bodies.get_mut(body.entity)
+        else {
+            continue;
+        };
+
+        *transform = body.transform;
+        // Rewinding teleports bodies instead of interpolating back to them.
+        if let Some(mut interpolation) = interpolation {
+            interpolation.teleport(transform.translation);
+        }
+
+        // Changing the velocity of a sleeping body would wake it.
+        let sleeping = body.sleeping.is_some();
+        if let (Some(mut velocity), Some(snapshot)) = (velocity, body.velocity) {
+            match sleeping {
+                true => *velocity.bypass_change_detection() = snapshot,
+                false => *velocity = snapshot,
+            }
+        }
+        if let (Some(mut acceleration), Some(snapshot)) = (acceleration, body.acceleration) {
+            match sleeping {
+                true => *acceleration.bypass_change_detection() = snapshot,
+                false => *acceleration = snapshot,
+            }
+        }
+        if let (Some(mut kinematic), Some(snapshot)) = (kinematic, body.kinematic) {
+            *kinematic = snapshot;
+        }
+        // Restored in place, replacing the components would exit every trigger.
+        if let (Some(mut targets), Some(snapshot)) = (trigger_targets, body.trigger_targets) {
+            targets.0 = snapshot;
+        }
+        if let (Some(mut overlaps), Some(snapshot)) = (trigger_overlaps, body.trigger_overlaps) {
+            overlaps.0 = snapshot;
+        }
+
+        let mut entity = commands.entity(body.entity);
+        match body.grounded {
+            Some(grounded) => entity.insert(grounded),
+            None => entity.remove::<Grounded>(),
+        };
+        match body.brushing_left {
+            Some(brushing) => entity.insert(brushing),
+            None => entity.remove::<BrushingLeft>(),
+        };
+        match body.brushing_right {
+            Some(brushing) => entity.insert(brushing),
+            None => entity.remove::<BrushingRight>(),
+        };
+        match body.ceilinged {
+            Some(ceilinged) => entity.insert(ceilinged),
+            None => entity.remove::<Ceilinged>(),
+        };
+        match body.sleeping {
+            Some(sleeping) => entity.insert(sleeping),
+            None => entity.remove::<Sleeping>(),
+        };
+    }
+
+    for (entity, snapshot) in snapshot.maps {
+        let Ok(mut map) = maps.get_mut(entity) else {
+            continue;
+        };
+        *map = (*snapshot).clone();
+
+        // Bodies despawned or spawned since the snapshot was captured.
+        let in_map = |body| {
+            map_bodies
+                .get(body)
+                .is_ok_and(|(.., parent)| parent.get() == entity)
+        };
+        let removed = map.entities().filter(|e| !in_map(*e)).collect::<Vec<_>>();
+        for body in removed {
+            map.remove(body);
+        }
+        for (body, transform, collider, _) in map_bodies.iter() {
+            if in_map(body) && !map.contains(body) {
+                map.insert(SpatialData::from_entity(body, transform, collider, ()));
+            }
+        }
+    }
+
+    // Entries are moved to the restored transforms once they are propagated.
+    broadphase.clear();
+    for (entity, transform, collider, collision, trigger, dynamic) in broadphase_bodies.iter() {
+        if let Some(data) = BroadphaseData::new(dynamic, collision, trigger) {
+            broadphase.insert(SpatialData::from_entity(entity, transform, collider, data));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::{spatial, stats::PhysicsStats};
+    use super::*;
+    use bevy::ecs::system::RunSystemOnce;
+
+    #[test]
+    fn restore_keeps_maps_in_sync_with_bodies() {
+        let mut world = World::new();
+        world.init_resource::<DynamicBroadphase>();
+        world.init_resource::<PhysicsStats>();
+        world.insert_resource(PhysicsHistory::default());
+
+        let collider = Collider::from_rect(Vec2::ZERO, Vec2::splat(8.));
+        let map = world.spawn(SpatialHash::<()>::new(16.)).id();
+        let despawned_static = world
+            .spawn((StaticBody, collider, GlobalTransform::default()))
+            .set_parent(map)
+            .id();
+        let dynamic = (
+            collider,
+            Transform::default(),
+            GlobalTransform::default(),
+            DynamicBody,
+            CollisionLayers::default(),
+        );
+        let kept_dynamic = world.spawn(dynamic).id();
+        let despawned_dynamic = world.spawn(dynamic).id();
+        world
+            .run_system_once(spatial::store_static_body_in_spatial_map)
+            .unwrap();
+        world
+            .run_system_once(spatial::update_dynamic_broadphase)
+            .unwrap();
+        world.run_system_once(capture_physics_snapshot).unwrap();
+
+        world.entity_mut(despawned_static).despawn_recursive();
+        world.despawn(despawned_dynamic);
+        let spawned_static = world
+            .spawn((StaticBody, collider, GlobalTransform::default()))
+            .set_parent(map)
+            .id();
+        world
+            .run_system_once(spatial::store_static_body_in_spatial_map)
+            .unwrap();
+
+        assert!(world.resource_mut::<PhysicsHistory>().rewind(0));
+        world.run_system_once(restore_physics_snapshot).unwrap();
+
+        let map = world.get::<SpatialHash>(map).unwrap();
+        assert!(!map.contains(despawned_static));
+        assert!(map.contains(spawned_static));
+        let broadphase = world.resource::<DynamicBroadphase>();
+        assert!(broadphase.contains(kept_dynamic));
+        assert!(!broadphase.contains(despawned_dynamic));
+    }
+}
@@ -1,5 +1,13 @@
-use super::{collision::AbsoluteCollider, collision::Collider, collision::StaticBody};
-use bevy::{prelude::*, utils::hashbrown::HashMap};
+use super::{
+    collision::{AbsoluteCollider, Collider, DynamicBody, RectCollider, StaticBody},
+    layers::{CollisionLayers, TriggerLayers},
+    stats::PhysicsStats,
+};
+use bevy::{
+    ecs::{component::ComponentId, world::DeferredWorld},
+    prelude::*,
+    utils::hashbrown::HashMap,
+};
 
 #[derive(Debug, Clone, Copy)]
 pub struct SpatialData<D> {
@@ -23,10 +31,25 @@ impl<D> SpatialData<D> {
     }
 }
 
-#[derive(Debug, Component)]
+/// The closest intersection found by [`SpatialHash::ray_cast`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RayHit {
+    pub entity: Entity,
+    pub point: Vec2,
+    pub normal: Vec2,
+    pub distance: f32,
+}
+
+/// The inclusive range of cells overlapped by an object in a [`SpatialHash`].
+type CellRange = ((i32, i32), (i32, i32));
+
+#[derive(Debug, Clone, Component)]
 pub struct SpatialHash<D = ()> {
     cell_size: f32,
     objects: HashMap<(i32, i32), Vec<SpatialData<D>>>,
+    cells: HashMap<Entity, CellRange>,
+    /// Contains every cell ever occupied since the map was last cleared.
+    bounds: Option<CellRange>,
 }
 
 #[allow(dead_code)]
@@ -35,6 +58,8 @@ impl<D: Clone> SpatialHash<D> {
         SpatialHash {
             cell_size,
             objects: HashMap::default(),
+            cells: HashMap::default(),
+            bounds: None,
         }
     }
 
@@ -46,7 +71,33 @@ impl<D: Clone> SpatialHash<D> {
     }
 
     pub fn is_empty(&self) -> bool {
-        self.objects.is_empty()
+        self.cells.is_empty()
+    }
+
+    pub fn contains(&self, entity: Entity) -> bool {
+        self.cells.contains_key(&entity)
+    }
+
+    /// Returns every entity in the map.
+    pub fn entities(&self) -> impl Iterator<Item = Entity> + '_ {
+        self.cells.keys().copied()
+    }
+
+    /// Returns the entry of `entity`, if it is in the map.
+    pub fn get(&self, entity: Entity) -> Option<&SpatialData<D>> {
+        let (first, _) = self.cells.get(&entity)?;
+        self.objects.get(first)?.iter().find(|d| d.entity == entity)
+    }
+
+    pub fn cell_size(&self) -> f32 {
+        self.cell_size
+    }
+
+    /// Returns every occupied cell along with the number of objects in it.
+    pub fn occupied_cells(&self) -> impl Iterator<Item = ((i32, i32), usize)> + '_ {
+        self.objects
+            .iter()
+            .map(|(cell, objects)| (*cell, objects.len()))
     }
 
     fn hash(&self, position: &Vec2) -> (i32, i32) {
@@ -56,12 +107,32 @@ impl<D: Clone> SpatialHash<D> {
         )
     }
 
+    fn cell_range(&self, collider: &AbsoluteCollider) -> CellRange {
+        (
+            self.hash(&Vec2::new(collider.min_x(), collider.min_y())),
+            self.hash(&Vec2::new(collider.max_x(), collider.max_y())),
+        )
+    }
+
     /// Inserts spatial data into map.
     ///
-    /// Data will be added to all cells overlapped by data's [`AbsoluteCollider`].
+    /// Data will be added to all cells overlapped by data's [`AbsoluteCollider`]. If the entity
+    /// is already in the map, its previous entry is replaced.
     pub fn insert(&mut self, data: SpatialData<D>) {
-        let (min_x, min_y) = self.hash(&Vec2::new(data.collider.min_x(), data.collider.min_y()));
-        let (max_x, max_y) = self.hash(&Vec2::new(data.collider.max_x(), data.collider.max_y()));
+        if self.cells.contains_key(&data.entity) {
+            self.update(data);
+            return;
+        }
+
+        let range @ ((min_x, min_y), (max_x, max_y)) = self.cell_range(&data.collider);
+        self.cells.insert(data.entity, range);
+        self.bounds = Some(match self.bounds {
+            Some(((bmin_x, bmin_y), (bmax_x, bmax_y))) => (
+                (bmin_x.min(min_x), bmin_y.min(min_y)),
+                (bmax_x.max(max_x), bmax_y.max(max_y)),
+            ),
+            None => range,
+        });
 
         for y in min_y..=max_y {
             for x in min_x..=max_x {
@@ -70,82 +141,209 @@ impl<D: Clone> SpatialHash<D> {
         }
     }
 
-    pub fn remove(&mut self, collider: AbsoluteCollider) {
-        let (min_x, min_y) = self.hash(&Vec2::new(collider.min_x(), collider.min_y()));
-        let (max_x, max_y) = self.hash(&Vec2::new(collider.max_x(), collider.max_y()));
+    /// Removes `entity` from every cell it occupies, returning its data.
+    pub fn remove(&mut self, entity: Entity) -> Option<SpatialData<D>> {
+        let ((min_x, min_y), (max_x, max_y)) = self.cells.remove(&entity)?;
+
+        let mut removed = None;
+        for y in min_y..=max_y {
+            for x in min_x..=max_x {
+                let Some(cell) = self.objects.get_mut(&(x, y)) else {
+                    continue;
+                };
+
+                if let Some(i) = cell.iter().position(|d| d.entity == entity) {
+                    removed = Some(cell.swap_remove(i));
+                }
+
+                if cell.is_empty() {
+                    self.objects.remove(&(x, y));
+                }
+            }
+        }
+
+        removed
+    }
+
+    /// Replaces the entry of `data`'s entity with `data`.
+    ///
+    /// Entries are overwritten in place when the entity still overlaps the same cells, otherwise
+    /// it is moved into its new cells. Entities not in the map are inserted.
+    pub fn update(&mut self, data: SpatialData<D>) {
+        let range = self.cell_range(&data.collider);
+        if self.cells.get(&data.entity) != Some(&range) {
+            self.remove(data.entity);
+            self.insert(data);
+            return;
+        }
 
+        let ((min_x, min_y), (max_x, max_y)) = range;
         for y in min_y..=max_y {
             for x in min_x..=max_x {
-                let _ = self.objects.remove(&(x, y));
+                if let Some(entry) = self
+                    .objects
+                    .get_mut(&(x, y))
+                    .and_then(|cell| cell.iter_mut().find(|d| d.entity == data.entity))
+                {
+                    *entry = data.clone();
+                }
             }
         }
     }
 
     pub fn clear(&mut self) {
         self.objects.clear();
+        self.cells.clear();
+        self.bounds = None;
     }
 
-    pub fn objects_in_cell_mut<'a>(
-        &'a mut self,
+    /// Returns all objects in the cell containing `position` and its eight neighbours.
+    ///
+    /// Each object is returned once.
+    pub fn nearby_objects<'a>(
+        &'a self,
         position: &Vec2,
-    ) -> impl Iterator<Item = &'a mut SpatialData<D>> + 'a {
-        let cell = self.hash(position);
-
-        self.objects
-            .get_mut(&(cell.0, cell.1))
-            .into_iter()
-            .flatten()
+    ) -> impl Iterator<Item = &'a SpatialData<D>> + 'a {
+        let (x, y) = self.hash(position);
+        self.objects_in_cells(((x - 1, y - 1), (x + 1, y + 1)))
     }
 
-    pub fn nearby_objects<'a>(
+    /// Returns all objects in the cells overlapped by `rect`.
+    ///
+    /// Each object is returned once.
+    pub fn objects_in_rect<'a>(
         &'a self,
-        position: &Vec2,
+        rect: &RectCollider,
     ) -> impl Iterator<Item = &'a SpatialData<D>> + 'a {
-        let cell = self.hash(position);
+        self.objects_in_cells((
+            self.hash(&Vec2::new(rect.tl.x, rect.br().y)),
+            self.hash(&Vec2::new(rect.br().x, rect.tl.y)),
+        ))
+    }
 
-        (-1..=1).flat_map(move |dx| {
-            (-1..=1).flat_map(move |dy| {
+    /// Objects spanning several cells are only returned from the first cell of `range` they
+    /// overlap.
+    fn objects_in_cells<'a>(
+        &'a self,
+        ((min_x, min_y), (max_x, max_y)): CellRange,
+    ) -> impl Iterator<Item = &'a SpatialData<D>> + 'a {
+        (min_y..=max_y).flat_map(move |y| {
+            (min_x..=max_x).flat_map(move |x| {
                 self.objects
-                    .get(&(cell.0 + dx, cell.1 + dy))
+                    .get(&(x, y))
                     .into_iter()
                     .flatten()
+                    .filter(move |data| {
+                        let ((first_x, first_y), _) = self.cell_range(&data.collider);
+                        (first_x.max(min_x), first_y.max(min_y)) == (x, y)
+                    })
             })
         })
     }
 
-    /// Returns false if encountered collision along path.
-    pub fn ray_cast(&self, start: Vec2, end: Vec2, samples: usize) -> bool {
-        let samples = (0..samples)
-            .map(|i| start.lerp(end, i as f32 / samples as f32))
-            .collect::<Vec<_>>();
-        for sample in samples.iter() {
-            if let Some(cells) = self.objects.get(&(self.hash(sample))) {
-                for cell in cells.iter() {
-                    for sample in samples.iter() {
-                        if cell.collider.contains(sample) {
-                            return false;
-                        }
-                    }
+    /// Casts `ray` through the map, returning the closest hit within `max_distance`.
+    ///
+    /// Only objects accepted by `filter` are tested, which can be used to ignore entities
+    /// or layers stored in the same map.
+    ///
+    /// Cells are visited in order along the ray, so the search stops as soon as a hit is closer
+    /// than the next cell boundary. Only the cells between the first and last occupied cells
+    /// along the ray are visited. Returns `None` if `max_distance` is not finite.
+    pub fn ray_cast(
+        &self,
+        ray: Ray2d,
+        max_distance: f32,
+        filter: impl Fn(&SpatialData<D>) -> bool,
+    ) -> Option<RayHit> {
+        if !max_distance.is_finite() {
+            return None;
+        }
+
+        let direction = *ray.direction;
+        let ((min_x, min_y), (max_x, max_y)) = self.bounds?;
+        let min = Vec2::new(min_x as f32, min_y as f32) * self.cell_size;
+        let max = Vec2::new((max_x + 1) as f32, (max_y + 1) as f32) * self.cell_size;
+
+        // Clips the ray against the occupied bounds.
+        let mut enter = 0f32;
+        let mut leave = max_distance;
+        for (origin, direction, min, max) in [
+            (ray.origin.x, direction.x, min.x, max.x),
+            (ray.origin.y, direction.y, min.y, max.y),
+        ] {
+            if direction == 0. {
+                if origin < min || origin > max {
+                    return None;
                 }
+                continue;
             }
+
+            let (a, b) = ((min - origin) / direction, (max - origin) / direction);
+            enter = enter.max(a.min(b));
+            leave = leave.min(a.max(b));
+        }
+        if enter > leave {
+            return None;
         }
 
-        true
-    }
+        let start = self.hash(&ray.get_point(enter));
+        let mut cell = (start.0.clamp(min_x, max_x), start.1.clamp(min_y, max_y));
 
-    pub fn nearby_objects_mut<'a>(
-        &'a mut self,
-        position: &Vec2,
-    ) -> impl Iterator<Item = &'a mut SpatialData<D>> + 'a {
-        let cell = self.hash(position);
+        let step = (direction.x.signum() as i32, direction.y.signum() as i32);
+        let delta = Vec2::new(
+            self.cell_size / direction.x.abs(),
+            self.cell_size / direction.y.abs(),
+        );
+        let boundary = |cell: i32, origin: f32, direction: f32| {
+            if direction > 0. {
+                ((cell + 1) as f32 * self.cell_size - origin) / direction
+            } else if direction < 0. {
+                (cell as f32 * self.cell_size - origin) / direction
+            } else {
+                f32::INFINITY
+            }
+        };
+        let mut next = Vec2::new(
+            boundary(cell.0, ray.origin.x, direction.x),
+            boundary(cell.1, ray.origin.y, direction.y),
+        );
 
-        let dx = cell.0 - 1..=cell.0 + 1;
-        let dy = cell.1 - 1..=cell.1 + 1;
+        let mut closest: Option<RayHit> = None;
+        loop {
+            for data in self.objects.get(&cell).into_iter().flatten() {
+                if !filter(data) {
+                    continue;
+                }
 
-        self.objects
-            .iter_mut()
-            .filter_map(move |(k, v)| (dx.contains(&k.0) && dy.contains(&k.1)).then_some(v))
-            .flatten()
+                if let Some((distance, normal)) =
+                    data.collider.ray_intersection(ray.origin, direction)
+                {
+                    if distance <= max_distance && closest.is_none_or(|c| distance < c.distance) {
+                        closest = Some(RayHit {
+                            entity: data.entity,
+                            point: ray.get_point(distance),
+                            normal,
+                            distance,
+                        });
+                    }
+                }
+            }
+
+            let exit = next.x.min(next.y);
+            if closest.is_some_and(|c| c.distance <= exit) || exit > leave {
+                break;
+            }
+
+            if next.x < next.y {
+                cell.0 += step.0;
+                next.x += delta.x;
+            } else {
+                cell.1 += step.1;
+                next.y += delta.y;
+            }
+        }
+
+        closest
     }
 }
 
@@ -165,3 +363,247 @@ pub fn store_static_body_in_spatial_map(
         }
     }
 }
+
+/// The layers a body in the [`DynamicBroadphase`] interacts with.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct BroadphaseData {
+    /// Only set for [`DynamicBody`] entities.
+    pub collision: Option<CollisionLayers>,
+    pub trigger: Option<TriggerLayers>,
+}
+
+impl BroadphaseData {
+    pub(crate) fn new(
+        dynamic: bool,
+        collision: Option<&CollisionLayers>,
+        trigger: Option<&TriggerLayers>,
+    ) -> Option<Self> {
+        let data = Self {
+            collision: collision.copied().filter(|_| dynamic),
+            trigger: trigger.copied(),
+        };
+
+        (data.collision.is_some() || data.trigger.is_some()).then_some(data)
+    }
+}
+
+/// Every [`DynamicBody`] and every other [`Collider`] with [`TriggerLayers`], shared by dynamic
+/// body collisions and trigger checks.
+///
+/// Persists between ticks. Entries are only updated when a body's transform, collider or layers
+/// change, and are removed as soon as the body is despawned or loses its collider.
+#[derive(Debug, Resource, Deref, DerefMut)]
+pub struct DynamicBroadphase(SpatialHash<BroadphaseData>);
+
+impl Default for DynamicBroadphase {
+    fn default() -> Self {
+        Self(SpatialHash::new(32.))
+    }
+}
+
+/// Removes an entity from the [`DynamicBroadphase`] when it loses its [`Collider`],
+/// [`DynamicBody`] or [`TriggerLayers`].
+///
+/// Once the removal is applied, the entity is inserted again if its remaining components still
+/// belong in the broadphase.
+pub(crate) fn remove_from_broadphase(mut world: DeferredWorld, entity: Entity, _: ComponentId) {
+    if let Some(mut broadphase) = world.get_resource_mut::<DynamicBroadphase>() {
+        broadphase.remove(entity);
+    }
+
+    world.commands().queue(move |world: &mut World| {
+        let Ok(body) = world.get_entity(entity) else {
+            return;
+        };
+        let (Some(transform), Some(collider)) =
+            (body.get::<GlobalTransform>(), body.get::<Collider>())
+        else {
+            return;
+        };
+        let Some(data) = BroadphaseData::new(
+            body.contains::<DynamicBody>(),
+            body.get::<CollisionLayers>(),
+            body.get::<TriggerLayers>(),
+        ) else {
+            return;
+        };
+
+        let data = SpatialData::from_entity(entity, transform, collider, data);
+        if let Some(mut broadphase) = world.get_resource_mut::<DynamicBroadphase>() {
+            broadphase.insert(data);
+        }
+    });
+}
+
+pub(crate) fn update_dynamic_broadphase(
+    mut broadphase: ResMut<DynamicBroadphase>,
+    changed: Query<
+        (
+            Entity,
+            &GlobalTransform,
+            &Collider,
+            Option<&CollisionLayers>,
+            Option<&TriggerLayers>,
+            Has<DynamicBody>,
+        ),
+        Or<(
+            Changed<GlobalTransform>,
+            Changed<Collider>,
+            Changed<CollisionLayers>,
+            Changed<TriggerLayers>,
+            Added<DynamicBody>,
+        )>,
+    >,
+    mut stats: ResMut<PhysicsStats>,
+) {
+    for (entity, transform, collider, collision, trigger, dynamic) in changed.iter() {
+        match BroadphaseData::new(dynamic, collision, trigger) {
+            Some(data) => {
+                broadphase.insert(SpatialData::from_entity(entity, transform, collider, data));
+                stats.broadphase_updates += 1;
+            }
+            None => {
+                broadphase.remove(entity);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn data(index: u32, tl: Vec2, size: Vec2) -> SpatialData<()> {
+        SpatialData {
+            entity: Entity::from_raw(index),
+            collider: Collider::from_rect(tl, size).absolute(&Transform::default()),
+            data: (),
+        }
+    }
+
+    fn entities<'a>(objects: impl Iterator<Item = &'a SpatialData<()>>) -> Vec<u32> {
+        let mut entities = objects.map(|d| d.entity.index()).collect::<Vec<_>>();
+        entities.sort_unstable();
+        entities
+    }
+
+    #[test]
+    fn remove_keeps_cell_neighbours() {
+        let mut map = SpatialHash::new_with(
+            16.,
+            [
+                data(0, Vec2::new(0., 16.), Vec2::splat(8.)),
+                data(1, Vec2::new(4., 12.), Vec2::splat(8.)),
+            ],
+        );
+
+        assert!(map.remove(Entity::from_raw(0)).is_some());
+        assert!(map.remove(Entity::from_raw(0)).is_none());
+        assert!(map.contains(Entity::from_raw(1)));
+        assert_eq!(entities(map.nearby_objects(&Vec2::new(8., 8.))), [1]);
+    }
+
+    #[test]
+    fn objects_spanning_cells_are_returned_once() {
+        let map = SpatialHash::new_with(16., [data(0, Vec2::new(-20., 20.), Vec2::splat(40.))]);
+        let rect = RectCollider {
+            tl: Vec2::new(-32., 32.),
+            size: Vec2::splat(64.),
+        };
+
+        assert!(map.occupied_cells().count() > 1);
+        assert_eq!(entities(map.objects_in_rect(&rect)), [0]);
+        assert_eq!(entities(map.nearby_objects(&Vec2::ZERO)), [0]);
+    }
+
+    #[test]
+    fn update_moves_between_cells() {
+        let mut map = SpatialHash::new_with(16., [data(0, Vec2::new(2., 14.), Vec2::splat(8.))]);
+        map.update(data(0, Vec2::new(100., 120.), Vec2::splat(8.)));
+
+        assert_eq!(map.occupied_cells().collect::<Vec<_>>(), [((6, 7), 1)]);
+        assert!(entities(map.nearby_objects(&Vec2::new(4., 12.))).is_empty());
+        assert_eq!(entities(map.nearby_objects(&Vec2::new(104., 116.))), [0]);
+        assert_eq!(
+            map.get(Entity::from_raw(0)).unwrap().collider.position(),
+            Vec2::new(100., 120.)
+        );
+    }
+
+    #[test]
+    fn ray_cast_hit() {
+        let map = SpatialHash::new_with(16., [data(0, Vec2::new(64., 8.), Vec2::splat(16.))]);
+        let hit = map
+            .ray_cast(Ray2d::new(Vec2::ZERO, Dir2::X), 100., |_| true)
+            .unwrap();
+
+        assert_eq!(hit.entity, Entity::from_raw(0));
+        assert_eq!(hit.distance, 64.);
+        assert_eq!(hit.normal, Vec2::NEG_X);
+    }
+
+    #[test]
+    fn ray_cast_miss() {
+        let map = SpatialHash::new_with(16., [data(0, Vec2::new(64., 8.), Vec2::splat(16.))]);
+
+        assert!(map
+            .ray_cast(Ray2d::new(Vec2::ZERO, Dir2::X), 32., |_| true)
+            .is_none());
+        assert!(map
+            .ray_cast(Ray2d::new(Vec2::ZERO, Dir2::NEG_X), 1000., |_| true)
+            .is_none());
+        assert!(map
+            .ray_cast(Ray2d::new(Vec2::ZERO, Dir2::X), 100., |_| false)
+            .is_none());
+    }
+
+    #[test]
+    fn ray_cast_infinite_distance() {
+        let empty = SpatialHash::<()>::new(16.);
+        assert!(empty
+            .ray_cast(Ray2d::new(Vec2::ZERO, Dir2::X), 1e30, |_| true)
+            .is_none());
+
+        let map = SpatialHash::new_with(16., [data(0, Vec2::new(64., 8.), Vec2::splat(16.))]);
+        assert!(map
+            .ray_cast(Ray2d::new(Vec2::ZERO, Dir2::X), f32::INFINITY, |_| true)
+            .is_none());
+        assert!(map
+            .ray_cast(Ray2d::new(Vec2::ZERO, Dir2::NEG_X), 1e30, |_| true)
+            .is_none());
+        assert!(map
+            .ray_cast(Ray2d::new(Vec2::new(-1e20, 0.), Dir2::X), 1e30, |_| true)
+            .is_some());
+    }
+
+    #[test]
+    fn removed_bodies_leave_broadphase() {
+        use bevy::ecs::system::RunSystemOnce;
+
+        let mut world = World::new();
+        world.init_resource::<DynamicBroadphase>();
+        world.init_resource::<PhysicsStats>();
+        let body = (
+            Collider::from_rect(Vec2::ZERO, Vec2::splat(8.)),
+            GlobalTransform::default(),
+            DynamicBody,
+            CollisionLayers::default(),
+            TriggerLayers::default(),
+        );
+        let despawned = world.spawn(body).id();
+        let trigger = world.spawn(body).id();
+        let removed = world.spawn(body).id();
+        world.run_system_once(update_dynamic_broadphase).unwrap();
+
+        world.despawn(despawned);
+        world.entity_mut(trigger).remove::<DynamicBody>();
+        world.entity_mut(removed).remove::<Collider>();
+        world.flush();
+
+        let broadphase = world.resource::<DynamicBroadphase>();
+        assert!(!broadphase.contains(despawned));
+        assert!(!broadphase.contains(removed));
+        let trigger = broadphase.get(trigger).unwrap();
+        assert!(trigger.data.collision.is_none() && trigger.data.trigger.is_some());
+    }
+}
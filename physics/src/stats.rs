@@ -0,0 +1,250 @@
+use super::{
+    collision::{DynamicBody, KinematicBody, StaticBody},
+    sleep::Sleeping,
+    spatial::SpatialHash,
+    trigger::Trigger,
+};
+use bevy::{
+    ecs::system::{Adapt, IntoAdapterSystem},
+    prelude::*,
+    utils::Instant,
+};
+use std::{fmt, io::Write, path::PathBuf, time::Duration};
+
+/// Counters and timings of the last [`Physics`](super::Physics) tick.
+///
+/// Counters accumulate over every substep of the tick.
+#[derive(Debug, Default, Clone, Resource)]
+pub struct PhysicsStats {
+    pub static_bodies: usize,
+    pub kinematic_bodies: usize,
+    pub dynamic_bodies: usize,
+    /// [`DynamicBody`] entities skipped because they are [`Sleeping`].
+    pub sleeping_bodies: usize,
+    pub triggers: usize,
+    /// Occupied cells over every static [`SpatialHash`].
+    pub hash_cells: usize,
+    /// Objects over every static [`SpatialHash`], counted once per cell they overlap.
+    pub hash_objects: usize,
+    /// Entries inserted or moved in the [`DynamicBroadphase`](super::spatial::DynamicBroadphase).
+    pub broadphase_updates: usize,
+    /// Collider pairs tested for overlap or swept against each other.
+    pub pair_tests: usize,
+    /// Collisions which moved a body.
+    pub resolutions: usize,
+    /// Overlaps between triggers and their targets, one per [`TriggerEvent`](super::trigger::TriggerEvent).
+    pub triggers_fired: usize,
+    /// Time spent in each system wrapped with [`timed`].
+    pub timings: Vec<(String, Duration)>,
+    /// Time spent in the whole tick.
+    pub tick: Duration,
+    started: Option<Instant>,
+}
+
+impl PhysicsStats {
+    fn record(&mut self, system: &str, elapsed: Duration) {
+        match self.timings.iter_mut().find(|(s, _)| s == system) {
+            Some((_, timing)) => *timing += elapsed,
+            None => self.timings.push((system.to_owned(), elapsed)),
+        }
+    }
+
+    /// The header matching [`PhysicsStats::csv_row`], without the timings of systems that have
+    /// not run yet.
+    pub fn csv_header(&self) -> String {
+        let mut header = String::from(
+            "static_bodies,kinematic_bodies,dynamic_bodies,sleeping_bodies,triggers,hash_cells,hash_objects,\
+             broadphase_updates,pair_tests,resolutions,triggers_fired,tick_us",
+        );
+        for (system, _) in self.timings.iter() {
+            header.push_str(&format!(",{system}_us"));
+        }
+
+        header
+    }
+
+    pub fn csv_row(&self) -> String {
+        let mut row = format!(
+            "{},{},{},{},{},{},{},{},{},{},{},{}",
+            self.static_bodies,
+            self.kinematic_bodies,
+            self.dynamic_bodies,
+            self.sleeping_bodies,
+            self.triggers,
+            self.hash_cells,
+            self.hash_objects,
+            self.broadphase_updates,
+            self.pair_tests,
+            self.resolutions,
+            self.triggers_fired,
+            self.tick.as_micros(),
+        );
+        for (_, timing) in self.timings.iter() {
+            row.push_str(&format!(",{}", timing.as_micros()));
+        }
+
+        row
+    }
+}
+
+impl fmt::Display for PhysicsStats {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(
+            f,
+            "bodies: {} static, {} kinematic, {} dynamic ({} sleeping), {} triggers",
+            self.static_bodies,
+            self.kinematic_bodies,
+            self.dynamic_bodies,
+            self.sleeping_bodies,
+            self.triggers
+        )?;
+        writeln!(
+            f,
+            "hash: {} cells, {} objects, {} broadphase updates",
+            self.hash_cells, self.hash_objects, self.broadphase_updates
+        )?;
+        writeln!(
+            f,
+            "pairs: {} tested, {} resolved, {} triggered",
+            self.pair_tests, self.resolutions, self.triggers_fired
+        )?;
+        write!(f, "tick: {:?}", self.tick)?;
+        for (system, timing) in self.timings.iter() {
+            write!(f, "\n  {system}: {timing:?}")?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Wraps `system` so that the time it takes to run is added to its [`PhysicsStats`] timing,
+/// named after the system without its module path.
+pub fn timed<M>(system: impl IntoSystem<(), (), M>) -> impl System<In = (), Out = ()> {
+    let system = IntoSystem::into_system(system);
+    let name = system.name();
+    let name = name.rsplit("::").next().unwrap_or(&name).to_owned();
+
+    IntoSystem::into_system(IntoAdapterSystem::new(Timed, system).pipe(
+        move |In(elapsed): In<Duration>, mut stats: ResMut<PhysicsStats>| {
+            stats.record(&name, elapsed)
+        },
+    ))
+}
+
+struct Timed;
+
+impl<S: System<In = (), Out = ()>> Adapt<S> for Timed {
+    type In = ();
+    type Out = Duration;
+
+    fn adapt(&mut self, _: (), run_system: impl FnOnce(())) -> Duration {
+        let started = Instant::now();
+        run_system(());
+        started.elapsed()
+    }
+}
+
+/// Periodically logs [`PhysicsStats`] and optionally appends them to a CSV file.
+///
+/// Reporting is disabled unless this resource is inserted.
+#[derive(Debug, Resource)]
+pub struct PhysicsStatsReport {
+    pub timer: Timer,
+    /// Truncated on the first write of a run and whenever the columns change, then written
+    /// with a header.
+    pub csv: Option<PathBuf>,
+    /// The header last written to `csv` during this run.
+    header: Option<String>,
+}
+
+impl PhysicsStatsReport {
+    pub fn every(duration: Duration) -> Self {
+        Self {
+            timer: Timer::new(duration, TimerMode::Repeating),
+            csv: None,
+            header: None,
+        }
+    }
+
+    pub fn with_csv(mut self, path: impl Into<PathBuf>) -> Self {
+        self.csv = Some(path.into());
+        self
+    }
+}
+
+pub(crate) fn reset_physics_stats(mut stats: ResMut<PhysicsStats>) {
+    let timings = core::mem::take(&mut stats.timings);
+    *stats = PhysicsStats {
+        // Keep the order of systems stable between ticks so that CSV columns line up.
+        timings: timings
+            .into_iter()
+            .map(|(s, _)| (s, Duration::ZERO))
+            .collect(),
+        started: Some(Instant::now()),
+        ..Default::default()
+    };
+}
+
+pub(crate) fn update_physics_stats(
+    mut stats: ResMut<PhysicsStats>,
+    static_bodies: Query<(), With<StaticBody>>,
+    kinematic_bodies: Query<(), With<KinematicBody>>,
+    dynamic_bodies: Query<(), With<DynamicBody>>,
+    sleeping_bodies: Query<(), (With<DynamicBody>, With<Sleeping>)>,
+    triggers: Query<(), With<Trigger>>,
+    maps: Query<&SpatialHash>,
+) {
+    stats.static_bodies = static_bodies.iter().count();
+    stats.kinematic_bodies = kinematic_bodies.iter().count();
+    stats.dynamic_bodies = dynamic_bodies.iter().count();
+    stats.sleeping_bodies = sleeping_bodies.iter().count();
+    stats.triggers = triggers.iter().count();
+
+    for map in maps.iter() {
+        for (_, objects) in map.occupied_cells() {
+            stats.hash_cells += 1;
+            stats.hash_objects += objects;
+        }
+    }
+
+    if let Some(started) = stats.started {
+        stats.tick = started.elapsed();
+    }
+}
+
+pub(crate) fn report_physics_stats(
+    stats: Res<PhysicsStats>,
+    mut report: ResMut<PhysicsStatsReport>,
+    time: Res<Time<Real>>,
+) {
+    if !report.timer.tick(time.delta()).just_finished() {
+        return;
+    }
+
+    info!("physics stats\n{}", *stats);
+
+    let PhysicsStatsReport { csv, header, .. } = &mut *report;
+    let Some(path) = csv.as_ref() else {
+        return;
+    };
+
+    let current = stats.csv_header();
+    let rewrite = header.as_ref() != Some(&current);
+    let result = std::fs::OpenOptions::new()
+        .create(true)
+        .write(true)
+        .truncate(rewrite)
+        .append(!rewrite)
+        .open(path)
+        .and_then(|mut file| {
+            if rewrite {
+                writeln!(file, "{current}")?;
+            }
+            writeln!(file, "{}", stats.csv_row())
+        });
+
+    match result {
+        Ok(()) => *header = Some(current),
+        Err(e) => error!("failed to export physics stats to {}: {e}", path.display()),
+    }
+}
@@ -0,0 +1,142 @@
+use bevy::{ecs::system::SystemParam, prelude::*};
+use bevy_tween::prelude::Interpolator;
+
+/// Scales the passage of time.
+///
+/// The global scale lives on the entity with [`GlobalTimeScale`], each [`TimeGroup`] has its own
+/// entity with a scale of its own. Use [`TimeScales`] to get the combined scale of an entity.
+#[derive(Debug, Component)]
+pub struct TimeScale(pub f32);
+
+/// Marks the entity holding the [`TimeScale`] applied to everything.
+#[derive(Debug, Default, Component)]
+pub struct GlobalTimeScale;
+
+/// A named group of entities sharing a [`TimeScale`].
+///
+/// Entities without a group belong to [`TimeGroup::World`].
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash, Component)]
+pub enum TimeGroup {
+    #[default]
+    World,
+    Player,
+    Enemies,
+    Fx,
+}
+
+impl TimeGroup {
+    pub const ALL: [Self; 4] = [Self::World, Self::Player, Self::Enemies, Self::Fx];
+}
+
+/// Scales the passage of time for a single entity, on top of its [`TimeGroup`] and the
+/// [`GlobalTimeScale`].
+#[derive(Debug, Clone, Copy, Component)]
+pub struct LocalTimeScale(pub f32);
+
+impl Default for LocalTimeScale {
+    fn default() -> Self {
+        Self(1.)
+    }
+}
+
+#[derive(Debug, Component)]
+pub struct TimeScaleRate {
+    start: f32,
+    end: f32,
+}
+
+impl TimeScaleRate {
+    pub fn new(start: f32, end: f32) -> Self {
+        Self { start, end }
+    }
+}
+
+impl Interpolator for TimeScaleRate {
+    type Item = TimeScale;
+
+    fn interpolate(&self, item: &mut Self::Item, value: f32) {
+        item.0 = self.start.lerp(self.end, value);
+    }
+}
+
+pub fn time_scale(start: f32, end: f32) -> TimeScaleRate {
+    TimeScaleRate::new(start, end)
+}
+
+#[derive(Debug, Component)]
+pub struct LocalTimeScaleRate {
+    start: f32,
+    end: f32,
+}
+
+impl LocalTimeScaleRate {
+    pub fn new(start: f32, end: f32) -> Self {
+        Self { start, end }
+    }
+}
+
+impl Interpolator for LocalTimeScaleRate {
+    type Item = LocalTimeScale;
+
+    fn interpolate(&self, item: &mut Self::Item, value: f32) {
+        item.0 = self.start.lerp(self.end, value);
+    }
+}
+
+pub fn local_time_scale(start: f32, end: f32) -> LocalTimeScaleRate {
+    LocalTimeScaleRate::new(start, end)
+}
+
+pub(crate) fn spawn_time_scales(world: &mut World) {
+    world.spawn((TimeScale(1.), GlobalTimeScale));
+    for group in TimeGroup::ALL {
+        world.spawn((TimeScale(1.), group));
+    }
+}
+
+/// Combines the global, group and local time scales of entities.
+#[derive(SystemParam)]
+pub struct TimeScales<'w, 's> {
+    global: Single<'w, (Entity, &'static TimeScale), With<GlobalTimeScale>>,
+    groups: Query<'w, 's, (Entity, &'static TimeScale, &'static TimeGroup)>,
+    entities: Query<'w, 's, (Option<&'static TimeGroup>, Option<&'static LocalTimeScale>)>,
+}
+
+impl TimeScales<'_, '_> {
+    /// The scale applied to everything.
+    pub fn global(&self) -> f32 {
+        self.global.1 .0
+    }
+
+    /// The entity holding the global [`TimeScale`], to be tweened with [`TimeScaleRate`].
+    pub fn global_entity(&self) -> Entity {
+        self.global.0
+    }
+
+    /// The scale of `group`, including the global scale.
+    pub fn group(&self, group: TimeGroup) -> f32 {
+        self.global() * self.group_scale(group)
+    }
+
+    /// The entity holding the [`TimeScale`] of `group`, to be tweened with [`TimeScaleRate`].
+    pub fn group_entity(&self, group: TimeGroup) -> Option<Entity> {
+        self.groups
+            .iter()
+            .find_map(|(entity, _, g)| (*g == group).then_some(entity))
+    }
+
+    /// The scale of `entity`, combining the global scale, its [`TimeGroup`] and its
+    /// [`LocalTimeScale`].
+    pub fn of(&self, entity: Entity) -> f32 {
+        let (group, local) = self.entities.get(entity).unwrap_or_default();
+
+        self.group(group.copied().unwrap_or_default()) * local.map_or(1., |l| l.0)
+    }
+
+    fn group_scale(&self, group: TimeGroup) -> f32 {
+        self.groups
+            .iter()
+            .find_map(|(_, scale, g)| (*g == group).then_some(scale.0))
+            .unwrap_or(1.)
+    }
+}
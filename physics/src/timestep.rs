@@ -0,0 +1,55 @@
+use super::PhysicsSubstep;
+use bevy::prelude::*;
+
+/// Configures how often the [`super::Physics`] schedule runs.
+///
+/// The whole fixed main loop, including `FixedUpdate`, runs at `hz`. Within each tick, positions
+/// are integrated and collisions resolved `substeps` times with an equal share of the tick.
+#[derive(Debug, Clone, Copy, Resource)]
+pub struct PhysicsTimestep {
+    pub hz: f64,
+    pub substeps: u32,
+}
+
+impl Default for PhysicsTimestep {
+    fn default() -> Self {
+        Self {
+            hz: 64.,
+            substeps: 1,
+        }
+    }
+}
+
+pub(crate) fn apply_physics_timestep(
+    timestep: Res<PhysicsTimestep>,
+    mut time: ResMut<Time<Fixed>>,
+) {
+    time.set_timestep_hz(timestep.hz);
+}
+
+/// Runs the [`PhysicsSubstep`] schedule [`PhysicsTimestep::substeps`] times, dividing the
+/// current tick's [`Time`] between them.
+pub(crate) fn run_substeps(world: &mut World) {
+    let substeps = world.resource::<PhysicsTimestep>().substeps.max(1);
+    let tick = *world.resource::<Time>();
+
+    let start = tick.elapsed() - tick.delta();
+    let delta = tick.delta() / substeps;
+
+    let mut time = Time::<()>::default();
+    time.set_wrap_period(tick.wrap_period());
+    time.advance_to(start);
+
+    for i in 1..=substeps {
+        if i == substeps {
+            time.advance_to(tick.elapsed());
+        } else {
+            time.advance_by(delta);
+        }
+
+        *world.resource_mut::<Time>() = time;
+        world.run_schedule(PhysicsSubstep);
+    }
+
+    *world.resource_mut::<Time>() = tick;
+}
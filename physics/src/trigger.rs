@@ -1,22 +1,111 @@
 use std::marker::PhantomData;
 
 use super::{
-    collision::Collider,
-    layers::TriggersWith,
-    prelude::CollidesWith,
-    spatial::{SpatialData, SpatialHash},
+    collision::{Collider, CollidesWith},
+    layers::{LayerMask, PhysicsLayers, TriggerLayers},
+    sleep::Sleeping,
+    spatial::{DynamicBroadphase, SpatialData},
+    stats::PhysicsStats,
+};
+use bevy::{
+    ecs::{component::ComponentId, world::DeferredWorld},
+    prelude::*,
+    utils::hashbrown::HashMap,
 };
-use bevy::{prelude::*, utils::hashbrown::HashMap};
 
 /// Marks an entity as a [`TriggerEvent`] source.
 ///
 /// Can exist in combination with a [`StaticBody`] or [`DynamicBody`].
 ///
-/// Will trigger with any [`TriggersWith`] layer present in the entity.
+/// Will trigger with any entity whose [`TriggerLayers`] filters contain one of its memberships.
 #[derive(Debug, Default, Clone, Copy, Component)]
+#[require(TriggerTargets)]
 pub struct Trigger(pub Collider);
 
-/// A list of all entities within a trigger.
+/// The entities currently inside of a [`Trigger`], updated by [`emit_trigger_states`].
+///
+/// Removing a trigger exits all of its targets.
+#[derive(Debug, Default, Component)]
+#[component(on_remove = exit_targets)]
+pub struct TriggerTargets(pub(crate) smallvec::SmallVec<[Entity; 4]>);
+
+impl TriggerTargets {
+    pub fn entities(&self) -> &[Entity] {
+        &self.0
+    }
+
+    pub fn contains(&self, entity: Entity) -> bool {
+        self.0.contains(&entity)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+}
+
+fn exit_targets(mut world: DeferredWorld, entity: Entity, _: ComponentId) {
+    let Some(targets) = world.get::<TriggerTargets>(entity).map(|t| t.0.clone()) else {
+        return;
+    };
+
+    for target in targets {
+        exit(&mut world, entity, target);
+    }
+}
+
+/// A list of all triggers an entity is within, along with the [`TriggerLayers`] memberships
+/// of each.
+///
+/// Removing it exits every trigger the entity is within.
+#[derive(Debug, Default, Component)]
+#[component(on_remove = exit_triggers)]
+pub struct TriggerOverlaps(pub(crate) smallvec::SmallVec<[(Entity, LayerMask); 4]>);
+
+fn exit_triggers(mut world: DeferredWorld, entity: Entity, _: ComponentId) {
+    let Some(triggers) = world
+        .get::<TriggerOverlaps>(entity)
+        .map(|o| o.entities().collect::<smallvec::SmallVec<[Entity; 4]>>())
+    else {
+        return;
+    };
+
+    for trigger in triggers {
+        let Some(mut targets) = world.get_mut::<TriggerTargets>(trigger) else {
+            continue;
+        };
+
+        if let Some(i) = targets.0.iter().position(|t| *t == entity) {
+            targets.0.swap_remove(i);
+            exit(&mut world, trigger, entity);
+        }
+    }
+}
+
+fn exit(world: &mut DeferredWorld, trigger: Entity, target: Entity) {
+    world.send_event(TriggerExit { trigger, target });
+    world
+        .commands()
+        .trigger_targets(OnTriggerExit { trigger, target }, [trigger, target]);
+}
+
+impl TriggerOverlaps {
+    pub fn entities(&self) -> impl Iterator<Item = Entity> + '_ {
+        self.0.iter().map(|(e, _)| *e)
+    }
+
+    /// Triggers which are members of any layer in `mask`.
+    pub fn in_layers(&self, mask: LayerMask) -> impl Iterator<Item = Entity> + '_ {
+        self.0
+            .iter()
+            .filter(move |(_, m)| m.intersects(mask))
+            .map(|(e, _)| *e)
+    }
+}
+
+/// A list of all triggers in layer `T` an entity is within.
+///
+/// Copied out of [`TriggerOverlaps`] for every layer registered with
+/// [`super::layers::RegisterPhysicsLayer::register_trigger_layer`].
 #[derive(Component)]
 pub struct Triggers<T>(smallvec::SmallVec<[Entity; 4]>, PhantomData<T>);
 
@@ -30,9 +119,19 @@ impl<T> Triggers<T> {
     pub fn entities(&self) -> &[Entity] {
         &self.0
     }
+}
+
+pub(crate) fn sync_triggers<T: Component>(
+    layers: Res<PhysicsLayers>,
+    mut bodies: Query<(&TriggerOverlaps, &mut Triggers<T>)>,
+) {
+    let Some(mask) = layers.get::<T>() else {
+        return;
+    };
 
-    fn clear(&mut self) {
-        self.0.clear();
+    for (overlaps, mut triggers) in bodies.iter_mut() {
+        triggers.0.clear();
+        triggers.0.extend(overlaps.in_layers(mask));
     }
 }
 
@@ -53,89 +152,219 @@ pub struct TriggerEnter {
     pub target: Entity,
 }
 
-/// Fires when a trigger exits the target entity.
+/// Fires every tick a target stays within a trigger after entering it.
 ///
 /// Uses the [`TriggerEvent`] internally.
 #[derive(Debug, Clone, Copy, Event)]
+pub struct TriggerStay {
+    pub trigger: Entity,
+    pub target: Entity,
+}
+
+/// Fires when a trigger exits the target entity.
+///
+/// Also fires when either the trigger or the target is despawned while inside of each other.
+#[derive(Debug, Clone, Copy, Event)]
 pub struct TriggerExit {
     pub trigger: Entity,
     pub target: Entity,
 }
 
+/// Observer event triggered on both the trigger and the target when a [`TriggerEnter`] fires.
+#[derive(Debug, Clone, Copy, Event)]
+pub struct OnTriggerEnter {
+    pub trigger: Entity,
+    pub target: Entity,
+}
+
+/// Observer event triggered on both the trigger and the target when a [`TriggerExit`] fires.
+///
+/// The trigger or target may already be despawning.
+#[derive(Debug, Clone, Copy, Event)]
+pub struct OnTriggerExit {
+    pub trigger: Entity,
+    pub target: Entity,
+}
+
 pub fn emit_trigger_states(
+    mut commands: Commands,
+    mut triggers: Query<(Entity, &mut TriggerTargets, Option<&Sleeping>)>,
     mut enter: EventWriter<TriggerEnter>,
+    mut stay: EventWriter<TriggerStay>,
     mut exit: EventWriter<TriggerExit>,
     mut reader: EventReader<TriggerEvent>,
-    mut active: Local<HashMap<Entity, smallvec::SmallVec<[Entity; 4]>>>,
 ) {
-    let events = reader.read().collect::<Vec<_>>();
-
-    for event in events.iter() {
-        let entry = active.entry(event.trigger).or_default();
-        if !entry.contains(&event.target) {
-            enter.send(TriggerEnter {
-                trigger: event.trigger,
-                target: event.target,
+    let mut current = HashMap::<Entity, smallvec::SmallVec<[Entity; 4]>>::default();
+    for event in reader.read() {
+        current.entry(event.trigger).or_default().push(event.target);
+    }
+
+    for (trigger, mut targets, sleeping) in triggers.iter_mut() {
+        // Out of range triggers are not checked, their targets are kept as they were.
+        if sleeping == Some(&Sleeping::OutOfRange) {
+            continue;
+        }
+
+        let inside = current.remove(&trigger).unwrap_or_default();
+        if inside.is_empty() && targets.is_empty() {
+            continue;
+        }
+
+        for target in targets.0.iter().filter(|t| !inside.contains(t)) {
+            exit.send(TriggerExit {
+                trigger,
+                target: *target,
             });
-            entry.push(event.target);
+            commands.trigger_targets(
+                OnTriggerExit {
+                    trigger,
+                    target: *target,
+                },
+                [trigger, *target],
+            );
         }
-    }
 
-    active.retain(|entity, targets| {
-        if events
-            .iter()
-            .any(|ev| ev.trigger == *entity && targets.contains(&ev.target))
-        {
-            true
-        } else {
-            for target in targets.iter() {
-                exit.send(TriggerExit {
-                    trigger: *entity,
+        for target in inside.iter() {
+            if targets.contains(*target) {
+                stay.send(TriggerStay {
+                    trigger,
+                    target: *target,
+                });
+            } else {
+                enter.send(TriggerEnter {
+                    trigger,
                     target: *target,
                 });
+                commands.trigger_targets(
+                    OnTriggerEnter {
+                        trigger,
+                        target: *target,
+                    },
+                    [trigger, *target],
+                );
             }
-
-            false
         }
-    });
+
+        targets.0 = inside;
+    }
 }
 
-pub fn handle_triggers<T: Component>(
-    triggers: Query<(Entity, &GlobalTransform, &Trigger), With<T>>,
-    bodies: Query<(Entity, &GlobalTransform, &Collider, &TriggersWith<T>)>,
-    mut body_triggers: Query<&mut Triggers<T>>,
+pub fn handle_triggers(
+    triggers: Query<(
+        Entity,
+        &GlobalTransform,
+        &Trigger,
+        &TriggerLayers,
+        Option<&Sleeping>,
+    )>,
+    broadphase: Res<DynamicBroadphase>,
+    mut overlaps: Query<&mut TriggerOverlaps>,
     mut writer: EventWriter<TriggerEvent>,
+    mut stats: ResMut<PhysicsStats>,
 ) {
-    for mut trigger in body_triggers.iter_mut() {
-        trigger.clear();
+    for mut overlaps in overlaps.iter_mut() {
+        overlaps.0.clear();
     }
 
-    let dynamic_body_map = SpatialHash::new_with(
-        64.,
-        bodies
-            .iter()
-            .map(|(e, t, c, _)| SpatialData::from_entity(e, t, c, ())),
-    );
+    for (entity, transform, trigger, trigger_layers, sleeping) in triggers.iter() {
+        if trigger_layers.memberships == LayerMask::NONE || sleeping == Some(&Sleeping::OutOfRange)
+        {
+            continue;
+        }
 
-    for (entity, transform, trigger) in triggers.iter() {
         let collider = trigger.0.global_absolute(transform);
 
         for SpatialData {
             entity: e,
             collider: c,
-            ..
-        } in dynamic_body_map.nearby_objects(&collider.position())
+            data,
+        } in broadphase.objects_in_rect(&collider.get_aabb())
         {
-            if *e != entity && collider.collides_with(c) {
+            let Some(layers) = data.trigger.filter(|l| l.filters != LayerMask::NONE) else {
+                continue;
+            };
+
+            stats.pair_tests += 1;
+            if *e != entity && layers.triggers_with(trigger_layers) && collider.collides_with(c) {
+                stats.triggers_fired += 1;
                 writer.send(TriggerEvent {
                     trigger: entity,
                     target: *e,
                 });
 
-                if let Ok(mut triggers) = body_triggers.get_mut(*e) {
-                    triggers.0.push(entity);
+                if let Ok(mut overlaps) = overlaps.get_mut(*e) {
+                    overlaps.0.push((entity, trigger_layers.memberships));
                 }
             }
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bevy::ecs::event::Events;
+
+    #[test]
+    fn targets_enter_and_exit() {
+        let mut world = World::new();
+        world.init_resource::<DynamicBroadphase>();
+        world.init_resource::<PhysicsStats>();
+        world.init_resource::<Events<TriggerEvent>>();
+        world.init_resource::<Events<TriggerEnter>>();
+        world.init_resource::<Events<TriggerStay>>();
+        world.init_resource::<Events<TriggerExit>>();
+
+        let mut schedule = Schedule::default();
+        schedule.add_systems(
+            (
+                super::super::spatial::update_dynamic_broadphase,
+                handle_triggers,
+                emit_trigger_states,
+            )
+                .chain(),
+        );
+
+        let trigger = world
+            .spawn((
+                Trigger(Collider::from_rect(Vec2::ZERO, Vec2::splat(16.))),
+                TriggerLayers::new(LayerMask(1), LayerMask::NONE),
+                GlobalTransform::default(),
+            ))
+            .id();
+        let target = world
+            .spawn((
+                Collider::from_rect(Vec2::ZERO, Vec2::splat(8.)),
+                TriggerLayers::new(LayerMask::NONE, LayerMask(1)),
+                GlobalTransform::default(),
+            ))
+            .id();
+
+        macro_rules! drain {
+            ($event:ty) => {
+                world
+                    .resource_mut::<Events<$event>>()
+                    .drain()
+                    .map(|e| (e.trigger, e.target))
+                    .collect::<Vec<_>>()
+            };
+        }
+
+        schedule.run(&mut world);
+        assert_eq!(drain!(TriggerEnter), [(trigger, target)]);
+        assert!(world
+            .get::<TriggerTargets>(trigger)
+            .unwrap()
+            .contains(target));
+
+        schedule.run(&mut world);
+        assert!(drain!(TriggerEnter).is_empty());
+        assert_eq!(drain!(TriggerStay), [(trigger, target)]);
+
+        *world.get_mut::<GlobalTransform>(target).unwrap() =
+            GlobalTransform::from_xyz(100., 0., 0.);
+        schedule.run(&mut world);
+        assert_eq!(drain!(TriggerExit), [(trigger, target)]);
+        assert!(world.get::<TriggerTargets>(trigger).unwrap().is_empty());
+    }
+}
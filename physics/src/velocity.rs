@@ -1,4 +1,4 @@
-use super::TimeScale;
+use super::{collision::ContinuousCollision, sleep::Sleeping, TimeScales};
 use bevy::prelude::*;
 
 #[derive(Debug, Default, Clone, Copy, Component)]
@@ -9,28 +9,48 @@ pub struct Velocity(pub Vec2);
 #[derive(Debug, Clone, Copy, Component)]
 pub struct MaxVelocity(pub Vec2);
 
+/// Collects changes to an entity's [`Velocity`], applied once per tick by [`apply_acceleration`].
 #[derive(Debug, Default, Clone, Component)]
 #[require(Mass)]
 pub struct Acceleration {
     forces: Vec<Vec2>,
+    impulse: Vec2,
+    velocity_change: Vec2,
 }
 
 impl Acceleration {
+    /// Applies a continuous force, divided by [`Mass`] and scaled by the entity's time scale.
     pub fn apply_force(&mut self, force: Vec2) {
         self.forces.push(force);
     }
 
+    /// Applies an instant impulse, divided by [`Mass`] but independent of time scale.
+    pub fn apply_impulse(&mut self, impulse: Vec2) {
+        self.impulse += impulse;
+    }
+
+    /// Changes velocity directly, ignoring both [`Mass`] and time scale.
+    pub fn apply_velocity_change(&mut self, change: Vec2) {
+        self.velocity_change += change;
+    }
+
+    /// The sum of the forces applied this tick.
+    pub fn total_force(&self) -> Vec2 {
+        self.forces.iter().sum()
+    }
+
     pub fn apply(
         &self,
         weight: &Mass,
         velocity: &mut Velocity,
         max: Option<&MaxVelocity>,
-        scale: &TimeScale,
+        scale: f32,
     ) {
         self.forces
             .iter()
             .map(|f| f / weight.0)
-            .for_each(|a| velocity.0 += a * scale.0);
+            .for_each(|a| velocity.0 += a * scale);
+        velocity.0 += self.impulse / weight.0 + self.velocity_change;
 
         if let Some(max) = max {
             let maxabs = max.0.abs();
@@ -47,6 +67,13 @@ impl Acceleration {
     }
 }
 
+/// Exponentially slows down the [`Velocity`] of an entity, per axis.
+///
+/// Each axis loses the fraction `1 - e^(-damping * dt)` of its velocity over `dt` seconds,
+/// independently of the physics rate.
+#[derive(Debug, Default, Clone, Copy, Component)]
+pub struct LinearDamping(pub Vec2);
+
 /// Entity mass.
 #[derive(Debug, Clone, Copy, Component)]
 pub struct Mass(pub f32);
@@ -57,21 +84,55 @@ impl Default for Mass {
     }
 }
 
-pub fn apply_velocity(
-    mut query: Query<(
-        &mut Transform,
-        &mut Velocity,
-        &mut Acceleration,
-        &Mass,
-        Option<&MaxVelocity>,
-    )>,
-    time: Res<Time>,
-    scale: Single<&TimeScale>,
+/// Applies the forces collected in each [`Acceleration`] to its [`Velocity`], once per tick.
+pub fn apply_acceleration(
+    mut query: Query<
+        (
+            Entity,
+            &mut Velocity,
+            &mut Acceleration,
+            &Mass,
+            Option<&MaxVelocity>,
+        ),
+        Without<Sleeping>,
+    >,
+    scales: TimeScales,
 ) {
-    for (mut transform, mut velocity, mut acceleration, weight, max) in query.iter_mut() {
+    for (entity, mut velocity, mut acceleration, weight, max) in query.iter_mut() {
         // It doesn't seem like scale should be applied to acceleration, but it does.
-        acceleration.apply(weight, &mut velocity, max, &scale);
-        transform.translation += (velocity.0 * time.delta_secs() * scale.0).extend(0.);
+        acceleration.apply(weight, &mut velocity, max, scales.of(entity));
         acceleration.forces.clear();
+        acceleration.impulse = Vec2::ZERO;
+        acceleration.velocity_change = Vec2::ZERO;
+    }
+}
+
+/// Applies [`LinearDamping`] and integrates [`Velocity`] into translation, once per substep.
+pub fn apply_velocity(
+    mut query: Query<
+        (
+            Entity,
+            &mut Transform,
+            &mut Velocity,
+            Option<&LinearDamping>,
+            Option<&mut ContinuousCollision>,
+        ),
+        Without<Sleeping>,
+    >,
+    time: Res<Time>,
+    scales: TimeScales,
+) {
+    for (entity, mut transform, mut velocity, damping, continuous) in query.iter_mut() {
+        let delta = time.delta_secs() * scales.of(entity);
+        if let Some(damping) = damping {
+            velocity.0 *= (-damping.0 * delta).exp();
+        }
+
+        let step = velocity.0 * delta;
+        transform.translation += step.extend(0.);
+
+        if let Some(mut continuous) = continuous {
+            continuous.motion = step;
+        }
     }
 }
@@ -1,5 +1,5 @@
 use bevy::{prelude::*, utils::HashMap};
-use physics::TimeScale;
+use physics::TimeScales;
 use std::{hash::Hash, marker::PhantomData, time::Duration};
 
 pub struct AnimationPlugin<A> {
@@ -97,9 +97,9 @@ impl<A: Animation> AnimationController<A> {
         self.active_index.as_ref().map(|(dir, _, _)| dir)
     }
 
-    fn update(&mut self, time: &Time, scale: &TimeScale) {
+    fn update(&mut self, time: &Time, scale: f32) {
         self.timer
-            .tick(Duration::from_secs_f32(time.delta_secs() * scale.0));
+            .tick(Duration::from_secs_f32(time.delta_secs() * scale));
 
         if let Some((_, (start, end), index)) = &mut self.active_index {
             if self.timer.just_finished() {
@@ -139,12 +139,12 @@ impl<A: Animation + PartialEq> AnimationController<A> {
 }
 
 fn animation<A: Animation>(
-    mut query: Query<(&mut Sprite, &mut AnimationController<A>)>,
+    mut query: Query<(Entity, &mut Sprite, &mut AnimationController<A>)>,
     time: Res<Time>,
-    scale: Single<&TimeScale>,
+    scales: TimeScales,
 ) {
-    for (mut sprite, mut animation) in query.iter_mut() {
-        animation.update(&time, &scale);
+    for (entity, mut sprite, mut animation) in query.iter_mut() {
+        animation.update(&time, scales.of(entity));
         if let Some(index) = animation.index() {
             if let Some(atlas) = &mut sprite.texture_atlas {
                 atlas.index = index;
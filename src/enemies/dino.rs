@@ -2,7 +2,7 @@ use crate::health::HitBox;
 use crate::player::combo::ComboCollision;
 use crate::{animation::AnimationController, TILE_SIZE};
 use bevy::prelude::*;
-use physics::{prelude::*, trigger::Trigger};
+use physics::{prelude::*, trigger::Trigger, TimeGroup};
 use rand::Rng;
 use selector::SelectorTarget;
 
@@ -11,10 +11,11 @@ const SPEED: f32 = 50.;
 #[derive(Default, Component)]
 #[require(AnimationController<DinoAnimation>(animation_controller))]
 #[require(Velocity(velocity), DynamicBody, Collider(collider))]
-#[require(layers::CollidesWith<layers::Wall>)]
+#[require(layers::Enemy, layers::CollidesWith<layers::Wall>, layers::CollidesWith<layers::Enemy>)]
 #[require(Trigger(|| Trigger(collider())), HitBox(|| HitBox::ONE))]
 #[require(SelectorTarget, ComboCollision)]
 #[require(super::DespawnHooked)]
+#[require(TimeGroup(|| TimeGroup::Enemies))]
 pub struct Dino;
 
 impl Dino {
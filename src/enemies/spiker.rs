@@ -3,7 +3,7 @@ use crate::player::combo::ComboCollision;
 use crate::spire;
 use crate::{animation::AnimationController, TILE_SIZE};
 use bevy::prelude::*;
-use physics::{layers, prelude::*, trigger::Trigger};
+use physics::{layers, prelude::*, trigger::Trigger, TimeGroup};
 use selector::SelectorTarget;
 
 const SPEED: f32 = 25.;
@@ -11,11 +11,12 @@ const SPEED: f32 = 25.;
 #[derive(Default, Component)]
 #[require(AnimationController<SpikerAnimation>(animation_controller))]
 #[require(Velocity, DynamicBody, Collider(collider))]
-#[require(layers::CollidesWith<layers::Wall>)]
+#[require(layers::Enemy, layers::CollidesWith<layers::Wall>, layers::CollidesWith<layers::Enemy>)]
 #[require(Trigger(|| Trigger(collider())), HitBox(|| HitBox::ONE))]
 #[require(SelectorTarget, ComboCollision)]
 #[require(super::DespawnHooked)]
 #[require(PatrolTarget)]
+#[require(TimeGroup(|| TimeGroup::Enemies))]
 pub struct Spiker;
 
 #[derive(Default, Component)]
@@ -0,0 +1,24 @@
+use crate::{impl_plugin, spire, TILE_SIZE};
+use bevy::prelude::*;
+use physics::{collision::Collider, gravity::GravityZone, trigger::Trigger};
+
+impl_plugin!(GravityZonePlugin, |app: &mut App| {
+    app.add_systems(Update, spawn_gravity_zones);
+});
+
+fn spawn_gravity_zones(
+    mut commands: Commands,
+    zones: Query<(Entity, &spire::GravityZone), Added<spire::GravityZone>>,
+) {
+    for (entity, zone) in zones.iter() {
+        let size = Vec2::new(zone.width as f32, zone.height as f32) * TILE_SIZE;
+
+        commands.entity(entity).insert((
+            GravityZone {
+                direction: zone.updraft.then_some(Dir2::Y),
+                scale: zone.scale,
+            },
+            Trigger(Collider::from_rect(Vec2::ZERO, size)),
+        ));
+    }
+}
@@ -1,12 +1,13 @@
 use bevy::prelude::*;
 
+pub mod gravity_zone;
 pub mod wall_hook;
 
 pub struct EntityPlugin;
 
 impl Plugin for EntityPlugin {
     fn build(&self, app: &mut App) {
-        app.add_plugins(wall_hook::WallHookPlugin);
+        app.add_plugins((wall_hook::WallHookPlugin, gravity_zone::GravityZonePlugin));
     }
 }
 
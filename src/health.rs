@@ -10,20 +10,18 @@ pub struct HealthPlugin;
 
 impl Plugin for HealthPlugin {
     fn build(&self, app: &mut App) {
-        app.register_trigger_layer::<HitBox>()
-            .register_trigger_layer::<HitBox>()
-            .add_systems(
-                Physics,
-                (
-                    update_triggered_hitboxes,
-                    update_health,
-                    insert_dead,
-                    despawn_dead,
-                )
-                    .chain()
-                    .after(CollisionSystems::Resolution)
-                    .in_set(PhysicsSystems::Collision),
-            );
+        app.register_trigger_layer::<HitBox>().add_systems(
+            Physics,
+            (
+                update_triggered_hitboxes,
+                update_health,
+                insert_dead,
+                despawn_dead,
+            )
+                .chain()
+                .after(CollisionSystems::Resolution)
+                .in_set(PhysicsSystems::Collision),
+        );
     }
 }
 
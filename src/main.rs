@@ -12,6 +12,7 @@ use health::Dead;
 use physics::{
     gravity::Gravity,
     layers::{self},
+    material::{PhysicsMaterial, SurfaceTag},
     spatial::SpatialHash,
 };
 use player::{hook::OccludeHookTarget, PlayerHurtBox};
@@ -56,6 +57,28 @@ fn main() {
             ui::UiPlugin,
         ))
         .register_required_components::<spire::TileSolid, physics::collision::TilesetCollider>()
+        .register_required_components::<spire::TileOneWay, physics::collision::TilesetCollider>()
+        .register_required_components::<spire::TileOneWay, physics::collision::OneWay>()
+        .register_required_components::<spire::TileRampLeft, physics::collision::TilesetCollider>()
+        .register_required_components_with::<spire::TileRampLeft, physics::collision::TileShape>(
+            || physics::collision::TileShape::Ramp(physics::collision::Ramp::Left),
+        )
+        .register_required_components::<spire::TileRampRight, physics::collision::TilesetCollider>()
+        .register_required_components_with::<spire::TileRampRight, physics::collision::TileShape>(
+            || physics::collision::TileShape::Ramp(physics::collision::Ramp::Right),
+        )
+        .register_required_components::<spire::TileIce, physics::collision::TilesetCollider>()
+        .register_required_components_with::<spire::TileIce, PhysicsMaterial>(|| {
+            PhysicsMaterial::new(0., 0.25, SurfaceTag::new("ice"))
+        })
+        .register_required_components::<spire::TileSticky, physics::collision::TilesetCollider>()
+        .register_required_components_with::<spire::TileSticky, PhysicsMaterial>(|| {
+            PhysicsMaterial::new(0., 4., SurfaceTag::new("sticky"))
+        })
+        .register_required_components::<spire::TileBouncy, physics::collision::TilesetCollider>()
+        .register_required_components_with::<spire::TileBouncy, PhysicsMaterial>(|| {
+            PhysicsMaterial::new(0.8, 1., SurfaceTag::new("bouncy"))
+        })
         .add_systems(Update, tween::despawn_finished_tweens)
         // .insert_resource(AlignCanvasToCamera(false))
         .register_required_components_with::<LevelTileSets, SpatialHash>(|| SpatialHash::new(32.))
@@ -21,7 +21,7 @@ use bevy_tween::{
     tween::IntoTarget,
 };
 use leafwing_input_manager::prelude::ActionState;
-use physics::{prelude::Collider, time_scale, TimeScale};
+use physics::{prelude::Collider, time_scale, TimeScales};
 use std::time::Duration;
 
 pub(super) fn death(
@@ -113,8 +113,7 @@ pub(super) fn update_knockback(
     mut rumble_requests: EventWriter<GamepadRumbleRequest>,
     settings: Res<PlayerSettings>,
     time: Res<Time>,
-    time_scale_entity: Single<Entity, With<TimeScale>>,
-    time_scale: Single<&TimeScale>,
+    scales: TimeScales,
     mut timer: Local<Timer>,
     mut new_hit: Local<bool>,
 ) {
@@ -132,7 +131,7 @@ pub(super) fn update_knockback(
 
         animations.set_animation_one_shot(PlayerAnimation::Hit);
 
-        let scale = time_scale_entity.into_target();
+        let scale = scales.global_entity().into_target();
         commands
             .animation()
             .insert(sequence((
@@ -181,7 +180,9 @@ pub(super) fn update_knockback(
             .insert(DespawnFinished);
     }
 
-    timer.tick(Duration::from_secs_f32(time.delta_secs() * time_scale.0));
+    timer.tick(Duration::from_secs_f32(
+        time.delta_secs() * scales.of(entity),
+    ));
     if timer.finished() {
         commands.entity(entity).remove::<Knockback>();
     }
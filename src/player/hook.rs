@@ -2,11 +2,12 @@ use super::movement::Homing;
 use super::PlayerSettings;
 use super::{Collider, Player, Velocity};
 use crate::health::Dead;
-use crate::TILE_SIZE;
 use bevy::prelude::*;
-use physics::layers::TriggersWith;
-use physics::spatial::SpatialHash;
+use physics::constraint::DistanceConstraint;
+use physics::layers::{PhysicsLayers, TriggersWith};
+use physics::query::SpatialQueryFilter;
 use physics::trigger::TriggerEnter;
+use physics::SpatialQuery;
 use selector::SelectorTarget;
 
 #[derive(Debug, Resource)]
@@ -48,8 +49,11 @@ pub struct Hook {
 #[derive(Component)]
 pub struct Chain;
 
-/// When combined with a [`SpatialHash`], performs ray
+/// When combined with a [`physics::spatial::SpatialHash`], performs ray
 /// casting on collision to occlude viable hook targets.
+///
+/// Registered as a collision layer, so removing it from the hash's
+/// [`physics::layers::CollisionLayers`] at runtime stops the occlusion.
 #[derive(Default, Component)]
 pub struct OccludeHookTarget;
 
@@ -85,7 +89,8 @@ pub(super) fn gather_viable_targets(
     targets: Query<(Entity, &GlobalTransform), With<SelectorTarget>>,
     player: Query<&GlobalTransform, With<super::Player>>,
     mut viable: ResMut<ViableTargets>,
-    spatial_hash_query: Query<&SpatialHash, With<OccludeHookTarget>>,
+    spatial_query: SpatialQuery,
+    physics_layers: Res<PhysicsLayers>,
     settings: Res<PlayerSettings>,
 ) {
     viable.0.clear();
@@ -94,6 +99,10 @@ pub(super) fn gather_viable_targets(
         return;
     };
 
+    let occluders = physics_layers
+        .get::<OccludeHookTarget>()
+        .unwrap_or_default();
+    let occluders = SpatialQueryFilter::default().with_layers(occluders);
     let mut targets: Vec<_> = targets
         .iter()
         .map(|(e, t)| {
@@ -107,13 +116,15 @@ pub(super) fn gather_viable_targets(
         })
         .filter(|t| t.2 < settings.target_threshold * settings.target_threshold)
         .filter(|t| {
-            spatial_hash_query.iter().all(|hash| {
-                let pxy = player.translation().xy();
-                let txy = t.1.translation().xy();
+            let pxy = player.translation().xy();
+            let txy = t.1.translation().xy();
 
-                let dist = pxy - txy;
-                hash.ray_cast(txy, pxy, (dist.length() / TILE_SIZE) as usize)
-            })
+            let Ok(direction) = Dir2::new(pxy - txy) else {
+                return true;
+            };
+            spatial_query
+                .ray_cast(Ray2d::new(txy, direction), txy.distance(pxy), &occluders)
+                .is_none()
         })
         .collect();
 
@@ -135,6 +146,7 @@ pub(super) fn move_hook(
             &Collider,
             &Velocity,
             Option<&super::Homing>,
+            Option<&DistanceConstraint>,
         ),
         With<Player>,
     >,
@@ -146,7 +158,8 @@ pub(super) fn move_hook(
         return;
     };
 
-    let Ok((player, player_collider, player_velocity, homing)) = player.get_single() else {
+    let Ok((player, player_collider, player_velocity, homing, constraint)) = player.get_single()
+    else {
         return;
     };
 
@@ -193,7 +206,23 @@ pub(super) fn move_hook(
     // };
 
     let mut moved = false;
-    if let Some(targ_selection) = homing.map(|h| h.target()) {
+    if let Some((constraint, anchor)) =
+        constraint.and_then(|c| c.ends().map(|(_, anchor)| (c, anchor)))
+    {
+        // Tethered, the chain follows the constraint once it has been solved.
+        moved = true;
+        hook_transform.translation.x = anchor.x;
+        hook_transform.translation.y = anchor.y;
+
+        let points = constraint.points(hook.chains.len());
+        for (chain, point) in hook.chains.iter().zip(points) {
+            let Ok(mut chain) = chains.get_mut(*chain) else {
+                continue;
+            };
+
+            chain.translation = point.extend(10.);
+        }
+    } else if let Some(targ_selection) = homing.map(|h| h.target()) {
         if let Ok((_, target, target_collider)) = collider_targets.get(targ_selection) {
             moved = true;
             let target = target.compute_transform();
@@ -272,6 +301,13 @@ pub(super) fn move_hook(
 //     }
 // }
 
+/// Releases the chain's [`DistanceConstraint`] along with the [`Homing`] that attached it.
+pub(super) fn release_hook(trigger: Trigger<OnRemove, Homing>, mut commands: Commands) {
+    if let Some(mut entity) = commands.get_entity(trigger.entity()) {
+        entity.remove::<DistanceConstraint>();
+    }
+}
+
 #[derive(Debug, Clone, Copy, Event)]
 pub struct HookTargetCollision {
     pub target: Entity,
@@ -16,7 +16,7 @@ use leafwing_input_manager::{
 };
 use movement::BrushingMove;
 use physics::{prelude::*, trigger::Trigger};
-use physics::{Physics, PhysicsSystems};
+use physics::{Physics, PhysicsSystems, TimeGroup};
 use selector::SelectorSprite;
 use std::hash::Hash;
 
@@ -40,6 +40,7 @@ pub struct PlayerSettings {
     pub wall_impulse: f32,
     pub walk_speed: f32,
     pub air_accel: f32,
+    /// Horizontal [`LinearDamping`] while airborne.
     pub air_damping: f32,
     pub slide_speed: f32,
     pub wall_stick_time: f32,
@@ -48,6 +49,9 @@ pub struct PlayerSettings {
     /// at which the player should break lock-on
     /// with a target when hitting a static body.
     pub break_angle: f32,
+    /// The fraction of the player's speed kept when bouncing off
+    /// a static body after breaking lock-on.
+    pub homing_bounce: f32,
 
     pub jump_speed: f32,
     pub jump_max_duration: f32,
@@ -61,6 +65,7 @@ pub struct PlayerSettings {
     pub target_threshold: f32,
 
     pub knockback_duration: f32,
+    /// Horizontal [`LinearDamping`] during knockback.
     pub knockback_damping: f32,
 }
 
@@ -71,10 +76,11 @@ impl Default for PlayerSettings {
             wall_impulse: 300.,
             walk_speed: 130.,
             air_accel: 0.15,
-            air_damping: 0.04,
+            air_damping: 2.6,
             slide_speed: 40.,
             wall_stick_time: 0.20,
             break_angle: 0.66,
+            homing_bounce: 0.3,
             jump_speed: 200.,
             jump_max_duration: 0.2,
             dash_duration: 0.1,
@@ -82,7 +88,7 @@ impl Default for PlayerSettings {
             dash_decay: 2.,
             target_threshold: 256.0,
             knockback_duration: 0.5,
-            knockback_damping: 0.04,
+            knockback_damping: 2.6,
         }
     }
 }
@@ -119,6 +125,7 @@ impl Plugin for PlayerPlugin {
         app.register_required_components::<crate::spire::Knight, Player>()
             .register_required_components::<SelectorTarget, TriggersWith<Homing>>()
             .register_trigger_layer::<Homing>()
+            .register_collision_layer::<hook::OccludeHookTarget>()
             .add_event::<hook::HookTargetCollision>()
             .init_resource::<hook::ViableTargets>()
             .init_resource::<::selector::SelectorTick>()
@@ -166,7 +173,6 @@ impl Plugin for PlayerPlugin {
                         hook::gather_viable_targets,
                         hook::move_hook,
                         selector::clear_removed_entities,
-                        ::selector::calculate_selectors,
                         selector::trigger_hook,
                         combo::combo,
                         camera::update_current_level,
@@ -176,12 +182,15 @@ impl Plugin for PlayerPlugin {
                 )
                     .chain(),
             )
+            .add_observer(hook::release_hook)
             .add_systems(PostUpdate, selector::add_selectors)
             .add_systems(
                 Physics,
                 (
                     camera::move_camera.before(PhysicsSystems::Velocity),
                     (
+                        // Timed into `PhysicsStats`, so it has to run within the tick they cover.
+                        physics::stats::timed(::selector::calculate_selectors),
                         selector::manage_offscreen_selectors,
                         selector::move_offscreen_indicators,
                     )
@@ -196,6 +205,7 @@ impl Plugin for PlayerPlugin {
 #[require(AnimationController<PlayerAnimation>(animation_controller), Direction)]
 #[require(ActionState<Action>, InputMap<Action>(input::input_map))]
 #[require(Velocity, Gravitational, DynamicBody, Collider(collider))]
+#[require(ContinuousCollision, LinearDamping, NeverSleep)]
 #[require(MaxVelocity(|| MaxVelocity(Vec2::splat(MAX_VEL))))]
 #[require(CameraOffset(|| CameraOffset(Vec2::new(TILE_SIZE / 2.0, TILE_SIZE * 2.))))]
 #[require(AnchorTarget)]
@@ -205,6 +215,7 @@ impl Plugin for PlayerPlugin {
 #[require(Combo)]
 #[require(::selector::SelectorSource)]
 #[require(Trigger(trigger))]
+#[require(TimeGroup(|| TimeGroup::Player))]
 pub struct Player;
 
 fn animation_controller() -> AnimationController<PlayerAnimation> {
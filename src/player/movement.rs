@@ -21,7 +21,7 @@ use core::f32;
 use interpolate::sprite_color_to;
 use leafwing_input_manager::prelude::ActionState;
 use physics::Physics;
-use physics::{prelude::*, TimeScale};
+use physics::{prelude::*, SpatialQuery, TimeScales};
 
 pub struct MovementPlugin;
 
@@ -41,7 +41,7 @@ impl Plugin for MovementPlugin {
                     wall_jump_impulse,
                     ground_strafe,
                     knockback,
-                    air_damping,
+                    damping,
                     homing_hitbox,
                     // debug,
                 )
@@ -97,23 +97,29 @@ fn homing(
                 &GlobalTransform,
                 &Collider,
                 &mut Velocity,
-                &TotalResolution,
+                &Collisions,
                 &Collision<layers::Wall>,
             ),
             With<Player>,
         >,
     >,
     target: Query<(&GlobalTransform, &Collider, Option<&Velocity>), Without<Player>>,
+    walls: Query<&PhysicsMaterial>,
     mut commands: Commands,
     settings: Res<PlayerSettings>,
     time: Res<Time>,
-    timescale: Single<&TimeScale>,
+    scales: TimeScales,
     mut hook_collision: EventReader<HookTargetCollision>,
 ) {
-    let timescale = timescale.into_inner();
-
-    let Some((player, mut homing, player_trans, player_collider, mut player_vel, res, collision)) =
-        player.map(|p| p.into_inner())
+    let Some((
+        player,
+        mut homing,
+        player_trans,
+        player_collider,
+        mut player_vel,
+        collisions,
+        collision,
+    )) = player.map(|p| p.into_inner())
     else {
         return;
     };
@@ -137,14 +143,24 @@ fn homing(
         }
     };
 
-    if !collision.entities().is_empty() {
-        let contact_normal = res.get().normalize_or_zero();
-        let bounce_dot = (contact_normal * -1.0).dot(vector);
+    let head_on = collision
+        .entities()
+        .iter()
+        .filter_map(|wall| collisions.get(*wall))
+        .min_by(|a, b| a.normal.dot(vector).total_cmp(&b.normal.dot(vector)));
+
+    if let Some(contact) = head_on {
+        let bounce_dot = (contact.normal * -1.0).dot(vector);
 
         if bounce_dot > settings.break_angle {
             commands.entity(player).remove::<Homing>();
-            // TODO: get a nice bounce
-            player_vel.0 = contact_normal * 100.;
+            let restitution = walls
+                .get(contact.entity)
+                .map_or(0., |m| m.restitution)
+                .max(settings.homing_bounce);
+            let incoming = contact.relative_velocity;
+            player_vel.0 =
+                (incoming - 2. * incoming.dot(contact.normal) * contact.normal) * restitution;
             return;
         }
     }
@@ -157,9 +173,9 @@ fn homing(
     match homing.state {
         HomingState::Hooking => {
             player_vel.0 = Vec2::default();
-            homing
-                .timer
-                .tick(Duration::from_secs_f32(time.delta_secs() * timescale.0));
+            homing.timer.tick(Duration::from_secs_f32(
+                time.delta_secs() * scales.of(player),
+            ));
 
             if homing.timer.just_finished() {
                 homing.state = HomingState::Moving;
@@ -279,6 +295,7 @@ fn brushing(
     player: Option<
         Single<
             (
+                Entity,
                 &mut Velocity,
                 &mut BrushingMove,
                 &Direction,
@@ -294,10 +311,10 @@ fn brushing(
         >,
     >,
     time: Res<Time>,
-    scale: Single<&TimeScale>,
+    scales: TimeScales,
     settings: Res<PlayerSettings>,
 ) {
-    let Some((mut velocity, mut brushing_move, direction, brushing_left, brushing_right)) =
+    let Some((entity, mut velocity, mut brushing_move, direction, brushing_left, brushing_right)) =
         player.map(|p| p.into_inner())
     else {
         return;
@@ -306,9 +323,9 @@ fn brushing(
     if (brushing_left.is_some() && *direction == Direction::Right)
         || (brushing_right.is_some() && *direction == Direction::Left)
     {
-        brushing_move
-            .0
-            .tick(Duration::from_secs_f32(time.delta_secs() * scale.0));
+        brushing_move.0.tick(Duration::from_secs_f32(
+            time.delta_secs() * scales.of(entity),
+        ));
     } else {
         brushing_move.0.reset();
     }
@@ -328,21 +345,46 @@ fn start_jump(
     mut commands: Commands,
     player: Option<
         Single<
-            (Entity, &ActionState<Action>, &GlobalTransform),
+            (
+                Entity,
+                &ActionState<Action>,
+                &GlobalTransform,
+                &Collider,
+                Has<Grounded>,
+            ),
             (
                 Or<(With<Grounded>, With<BrushingLeft>, With<BrushingRight>)>,
                 Without<Knockback>,
             ),
         >,
     >,
+    spatial_query: SpatialQuery,
+    one_way: Query<(), With<OneWay>>,
     server: Res<AssetServer>,
 ) {
-    let Some((entity, action_state, transform)) = player.map(|p| p.into_inner()) else {
+    let Some((entity, action_state, transform, collider, grounded)) =
+        player.map(|p| p.into_inner())
+    else {
         return;
     };
 
     for action in action_state.get_just_pressed() {
         if action == Action::Jump {
+            // down + jump drops through one way platforms
+            if grounded && action_state.clamped_axis_pair(&Action::Run).y < -0.5 {
+                let feet = collider.global_absolute(transform).get_aabb();
+                let standing_on = spatial_query.aabb_overlaps(
+                    Vec2::new(feet.tl.x, feet.br().y),
+                    Vec2::new(feet.size.x, 1.),
+                    &SpatialQueryFilter::default().without_entity(entity),
+                );
+
+                if standing_on.iter().any(|e| one_way.contains(*e)) {
+                    commands.entity(entity).insert(DropThrough);
+                    continue;
+                }
+            }
+
             commands.entity(entity).insert(Jumping);
 
             commands.spawn((
@@ -361,7 +403,7 @@ fn jumping(
     mut commands: Commands,
     player: Option<
         Single<
-            (Entity, &ActionState<Action>, &mut Velocity),
+            (Entity, &ActionState<Action>, &mut Velocity, Has<Ceilinged>),
             (
                 With<Player>,
                 With<Jumping>,
@@ -372,19 +414,24 @@ fn jumping(
         >,
     >,
     time: Res<Time>,
-    scale: Single<&TimeScale>,
+    scales: TimeScales,
     mut timer: Local<Option<Timer>>,
     settings: Res<PlayerSettings>,
 ) {
-    let Some((entity, action_state, mut velocity)) = player.map(|p| p.into_inner()) else {
+    let Some((entity, action_state, mut velocity, ceilinged)) = player.map(|p| p.into_inner())
+    else {
         return;
     };
 
     let timer = timer
         .get_or_insert_with(|| Timer::from_seconds(settings.jump_max_duration, TimerMode::Once));
 
-    timer.tick(Duration::from_secs_f32(time.delta_secs() * scale.0));
+    timer.tick(Duration::from_secs_f32(
+        time.delta_secs() * scales.of(entity),
+    ));
+    // Bumping into a ceiling cuts the jump short instead of pressing against it.
     if timer.finished()
+        || ceilinged
         || action_state
             .get_pressed()
             .iter()
@@ -403,7 +450,11 @@ fn jumping(
 fn wall_jump_impulse(
     player: Option<
         Single<
-            (&mut Velocity, Option<&BrushingLeft>, Option<&BrushingRight>),
+            (
+                &mut Acceleration,
+                Option<&BrushingLeft>,
+                Option<&BrushingRight>,
+            ),
             (
                 With<Player>,
                 Added<Jumping>,
@@ -415,14 +466,15 @@ fn wall_jump_impulse(
     >,
     settings: Res<PlayerSettings>,
 ) {
-    let Some((mut velocity, brushing_left, brushing_right)) = player.map(|p| p.into_inner()) else {
+    let Some((mut acceleration, brushing_left, brushing_right)) = player.map(|p| p.into_inner())
+    else {
         return;
     };
 
     if brushing_left.is_some() {
-        velocity.0.x += settings.wall_impulse;
+        acceleration.apply_impulse(Vec2::X * settings.wall_impulse);
     } else if brushing_right.is_some() {
-        velocity.0.x -= settings.wall_impulse;
+        acceleration.apply_impulse(Vec2::NEG_X * settings.wall_impulse);
     }
 }
 
@@ -462,7 +514,8 @@ fn dashing(
                 Entity,
                 &GlobalTransform,
                 &Sprite,
-                &mut Velocity,
+                &Velocity,
+                &mut Acceleration,
                 &ActionState<Action>,
                 Option<&Dashing>,
                 Option<&Grounded>,
@@ -472,7 +525,7 @@ fn dashing(
     >,
     mut reader: EventReader<HookTargetCollision>,
     time: Res<Time>,
-    scale: Single<&TimeScale>,
+    scales: TimeScales,
     mut timer: Local<Option<Timer>>,
     mut spawn_ghost_timer: Local<Option<Timer>>,
     mut ghost_z: Local<usize>,
@@ -480,7 +533,7 @@ fn dashing(
     mut last_dir: Local<Vec2>,
     settings: Res<PlayerSettings>,
 ) {
-    let Some((entity, transform, sprite, mut velocity, action_state, dash, grounded)) =
+    let Some((entity, transform, sprite, velocity, mut acceleration, action_state, dash, grounded)) =
         player.map(|p| p.into_inner())
     else {
         return;
@@ -508,22 +561,28 @@ fn dashing(
             let dash_timer = timer.get_or_insert_with(|| {
                 Timer::from_seconds(settings.dash_duration, TimerMode::Once)
             });
-            dash_timer.tick(Duration::from_secs_f32(time.delta_secs() * scale.0));
+            dash_timer.tick(Duration::from_secs_f32(
+                time.delta_secs() * scales.of(entity),
+            ));
             if dash_timer.finished() {
                 *dash_reset = false;
                 commands.entity(entity).remove::<Dashing>();
                 *timer = None;
-                velocity.0 /= settings.dash_decay;
+                acceleration.apply_velocity_change(velocity.0 / settings.dash_decay - velocity.0);
                 return;
             }
 
             let dash_vec = dash.0.unwrap_or_else(|| *last_dir);
-            velocity.0 = dash_vec.normalize_or_zero() * settings.dash_speed;
+            acceleration.apply_velocity_change(
+                dash_vec.normalize_or_zero() * settings.dash_speed - velocity.0,
+            );
 
             let ghost_timer = spawn_ghost_timer.get_or_insert_with(|| {
                 Timer::from_seconds(settings.dash_duration / 5., TimerMode::Repeating)
             });
-            ghost_timer.tick(Duration::from_secs_f32(time.delta_secs() * scale.0));
+            ghost_timer.tick(Duration::from_secs_f32(
+                time.delta_secs() * scales.of(entity),
+            ));
             if ghost_timer.just_finished() {
                 let ghost = commands
                     .spawn((
@@ -628,61 +687,87 @@ fn ground_strafe(
 fn wall_slide(
     player: Option<
         Single<
-            &mut Velocity,
+            (
+                &mut Velocity,
+                Option<&BrushingLeft>,
+                Option<&BrushingRight>,
+                Option<&PhysicsMaterial>,
+            ),
             (
                 With<Player>,
                 Or<(With<BrushingLeft>, With<BrushingRight>)>,
+                Without<Dashing>,
                 Without<Knockback>,
             ),
         >,
     >,
+    walls: Query<&PhysicsMaterial>,
     settings: Res<PlayerSettings>,
 ) {
-    let Some(mut velocity) = player else {
+    let Some((mut velocity, left, right, material)) = player.map(|p| p.into_inner()) else {
         return;
     };
 
-    velocity.0.y = velocity.0.y.max(-settings.slide_speed);
+    // Slide down slippery walls faster and sticky walls slower.
+    let friction = left
+        .map(|l| l.0)
+        .into_iter()
+        .chain(right.map(|r| r.0))
+        .map(|wall| {
+            material
+                .copied()
+                .unwrap_or_default()
+                .combine(&walls.get(wall).copied().unwrap_or_default())
+                .friction
+        })
+        .fold(0., f32::max);
+
+    velocity.0.y = velocity.0.y.max(-settings.slide_speed / friction.max(0.01));
 }
 
-fn air_damping(
+fn damping(
     player: Option<
         Single<
-            &mut Velocity,
             (
-                With<Player>,
-                Without<Grounded>,
-                Without<BrushingLeft>,
-                Without<BrushingRight>,
-                Without<Knockback>,
+                &mut LinearDamping,
+                Has<Grounded>,
+                Has<BrushingLeft>,
+                Has<BrushingRight>,
+                Has<Knockback>,
             ),
+            With<Player>,
         >,
     >,
     settings: Res<PlayerSettings>,
 ) {
-    let Some(mut velocity) = player else {
+    let Some((mut damping, grounded, left, right, knockback)) = player.map(|p| p.into_inner())
+    else {
         return;
     };
 
-    velocity.0.x *= 1.0 - settings.air_damping;
+    damping.0.x = if knockback {
+        settings.knockback_damping
+    } else if !grounded && !left && !right {
+        settings.air_damping
+    } else {
+        0.
+    };
 }
 
 fn knockback(
-    player: Option<Single<(&mut Velocity, &Knockback), With<Player>>>,
-    settings: Res<PlayerSettings>,
+    player: Option<Single<(&Velocity, &mut Acceleration, &Knockback), With<Player>>>,
     mut set: Local<bool>,
 ) {
-    let Some((mut velocity, knockback)) = player.map(|p| p.into_inner()) else {
+    let Some((velocity, mut acceleration, knockback)) = player.map(|p| p.into_inner()) else {
         *set = true;
         return;
     };
 
     if *set {
         *set = false;
-        velocity.0.x = knockback.normalized().x * 100.;
+        acceleration
+            .apply_velocity_change(Vec2::X * (knockback.normalized().x * 100. - velocity.0.x));
     }
-
-    velocity.0.x *= 1.0 - settings.knockback_damping;
 }
 
 fn debug(
@@ -6,19 +6,33 @@ use bevy::{prelude::*, sprite::Anchor, utils::HashMap};
 use bevy_pixel_gfx::camera::MainCamera;
 use leafwing_input_manager::prelude::ActionState;
 use physics::{
+    constraint::{ConstraintAnchor, DistanceConstraint},
     prelude::Collider,
     trigger::{Trigger, Triggers},
 };
 use selector::{MaxSelectors, Selector, SelectorInfo, SelectorTarget};
 
 pub(super) fn trigger_hook(
-    player: Query<(Entity, &Velocity, &ActionState<Action>, Option<&Homing>), With<Player>>,
+    player: Query<
+        (
+            Entity,
+            &GlobalTransform,
+            &Collider,
+            &Velocity,
+            &ActionState<Action>,
+            Option<&Homing>,
+        ),
+        With<Player>,
+    >,
+    targets: Query<(&GlobalTransform, Option<&Collider>)>,
     selectors: Query<(&Selector, &SelectorInfo)>,
     max_selectors: Res<MaxSelectors>,
     server: Res<AssetServer>,
     mut commands: Commands,
 ) {
-    let Ok((player_entity, player_velocity, action, homing)) = player.get_single() else {
+    let Ok((player_entity, player_transform, player_collider, player_velocity, action, homing)) =
+        player.get_single()
+    else {
         return;
     };
 
@@ -45,13 +59,27 @@ pub(super) fn trigger_hook(
         };
 
         if homing.is_none() {
+            let Ok((target_transform, target_collider)) = targets.get(target) else {
+                return;
+            };
+
             commands.spawn((
                 AudioPlayer::new(server.load("audio/sfx/hook.wav")),
                 PlaybackSettings::DESPAWN,
             ));
-            commands
-                .entity(player_entity)
-                .insert(super::Homing::new(target, player_velocity.0));
+
+            // The chain is tethered at its length when hooked, removed again with the `Homing`.
+            let target_center = target_collider.map_or(target_transform.translation().xy(), |c| {
+                c.global_absolute(target_transform).center()
+            });
+            let length = player_collider
+                .global_absolute(player_transform)
+                .center()
+                .distance(target_center);
+            commands.entity(player_entity).insert((
+                super::Homing::new(target, player_velocity.0),
+                DistanceConstraint::rope(ConstraintAnchor::Entity(target), length),
+            ));
         }
     }
 }